@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use pluggy::Client;
+use pluggy::{Client, ConnectorId};
 
 #[tokio::main]
 async fn main() {
@@ -9,7 +9,10 @@ async fn main() {
         ("user".to_string(), "user-ok".to_string()),
         ("password".to_string(), "password-ok".to_string()),
     ]);
-    let item = client.create_item(&api_key, 2, &parameters).await.unwrap();
+    let item = client
+        .create_item(&api_key, ConnectorId(2), &parameters)
+        .await
+        .unwrap();
 
     println!("Item: {:?}", item);
 }
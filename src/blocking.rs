@@ -0,0 +1,309 @@
+//! Synchronous client, enabled with the `blocking` feature.
+//!
+//! `blocking::Client` wraps an async `Client` and runs each call to
+//! completion on its own single-threaded tokio runtime, for programs that
+//! don't run one:
+//!
+//! ```no_run
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let (client, api_key) = pluggy::blocking::Client::new_from_env_with_api_key()?;
+//! let connectors = client.get_connectors(&api_key, false)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Its methods block the current thread, so they must not be called from
+//! async code: calling them inside a tokio runtime panics.
+
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use tokio::runtime::Runtime;
+
+use crate::limit::LimitMetricsSnapshot;
+use crate::pagination::{PageOptions, Paginated};
+use crate::{
+    Boleto, BoletoConnection, Category, CategoryId, CategoryRule, Connector, ConnectorFilter,
+    ConnectorId, Consent, CreateItemOptions, CreateSmartTransferPaymentOptions,
+    CreateSmartTransferPreauthorizationOptions, Item, ItemId, Money, NewBoleto, PaymentRequest,
+    SmartTransferPayment, SmartTransferPreauthorization, Transaction, ValidationResult, Webhook,
+    WebhookEvent, WebhookId,
+};
+
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+/// Blocking iterator over the results of every page of a list endpoint.
+pub struct PaginatedIter<'a, T> {
+    runtime: &'a Runtime,
+    stream: Paginated<'a, T>,
+}
+
+impl<'a, T: DeserializeOwned + 'a> PaginatedIter<'a, T> {
+    pub fn collect_all(self) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        self.collect()
+    }
+}
+
+impl<'a, T: DeserializeOwned + 'a> Iterator for PaginatedIter<'a, T> {
+    type Item = Result<T, Box<dyn std::error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Blocking versions of the async `Client` methods with the same name.
+macro_rules! blocking_methods {
+    ($(fn $name:ident(&self $(, $arg:ident: $type:ty)*) -> $output:ty;)*) => {
+        $(
+            pub fn $name(&self $(, $arg: $type)*) -> Result<$output, Box<dyn std::error::Error>> {
+                self.runtime.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Blocking versions of the `Client::stream_*` methods.
+macro_rules! blocking_streams {
+    ($(fn $name:ident(&self $(, $arg:ident: $type:ty)*) -> $output:ty;)*) => {
+        $(
+            pub fn $name<'a>(
+                &'a self
+                $(, $arg: $type)*,
+                options: PageOptions,
+            ) -> Result<PaginatedIter<'a, $output>, Box<dyn std::error::Error>> {
+                Ok(PaginatedIter {
+                    runtime: &self.runtime,
+                    stream: self.inner.$name($($arg,)* options)?,
+                })
+            }
+        )*
+    };
+}
+
+impl Client {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        Self::from_async(crate::Client::new(client_id, client_secret))
+    }
+
+    /// Wraps a client configured with `crate::Client::builder`.
+    pub fn from_async(client: crate::Client) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the blocking client runtime");
+
+        Self {
+            inner: client,
+            runtime,
+        }
+    }
+
+    pub fn new_from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_async(crate::Client::new_from_env()?))
+    }
+
+    pub fn new_from_env_with_api_key() -> Result<(Self, String), Box<dyn std::error::Error>> {
+        let client = Self::new_from_env()?;
+        let api_key = client.create_api_key()?;
+        Ok((client, api_key))
+    }
+
+    /// The async client calls are made with.
+    pub fn as_async(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    pub fn limit_metrics(&self) -> LimitMetricsSnapshot {
+        self.inner.limit_metrics()
+    }
+
+    blocking_methods! {
+        fn create_api_key(&self) -> String;
+        fn create_connect_token(&self, api_key: &str) -> String;
+        fn get_connectors(&self, api_key: &str, with_sandbox: bool) -> Vec<Connector>;
+        fn search_connectors(&self, api_key: &str, filter: &ConnectorFilter) -> Vec<Connector>;
+        fn get_connector(&self, api_key: &str, connector_id: ConnectorId) -> Connector;
+        fn get_item(&self, api_key: &str, item_id: ItemId) -> Item;
+        fn validate_parameters(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            parameters: &HashMap<&str, &str>
+        ) -> ValidationResult;
+        fn create_item(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            parameters: &HashMap<String, String>
+        ) -> Item;
+        fn create_item_with_options(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            parameters: &HashMap<String, String>,
+            options: &CreateItemOptions<'_>
+        ) -> Item;
+        fn update_item(
+            &self,
+            api_key: &str,
+            item_id: ItemId,
+            parameters: &HashMap<String, String>
+        ) -> Item;
+        fn update_item_mfa_credentials(
+            &self,
+            api_key: &str,
+            item_id: ItemId,
+            parameters: &HashMap<String, String>
+        ) -> Item;
+        fn delete_item(&self, api_key: &str, item_id: ItemId) -> ();
+        fn get_categories(&self, api_key: &str) -> Vec<Category>;
+        fn get_category(&self, api_key: &str, category_id: &CategoryId) -> Category;
+        fn get_category_rules(&self, api_key: &str) -> Vec<CategoryRule>;
+        fn create_category_rule(
+            &self,
+            api_key: &str,
+            description: &str,
+            category_id: &CategoryId
+        ) -> CategoryRule;
+        fn delete_category_rule(&self, api_key: &str, category_rule_id: &str) -> ();
+        fn update_transaction_category(
+            &self,
+            api_key: &str,
+            transaction_id: &str,
+            category_id: &CategoryId
+        ) -> Transaction;
+        fn get_webhooks(&self, api_key: &str) -> Vec<Webhook>;
+        fn get_webhook(&self, api_key: &str, webhook_id: WebhookId) -> Webhook;
+        fn create_webhook(&self, api_key: &str, url: &str, event: WebhookEvent) -> Webhook;
+        fn delete_webhook(&self, api_key: &str, webhook_id: WebhookId) -> ();
+        fn create_smart_transfer_preauthorization(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            parameters: &HashMap<String, String>,
+            recipient_ids: &[String]
+        ) -> SmartTransferPreauthorization;
+        fn create_smart_transfer_preauthorization_with_options(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            parameters: &HashMap<String, String>,
+            recipient_ids: &[String],
+            options: &CreateSmartTransferPreauthorizationOptions<'_>
+        ) -> SmartTransferPreauthorization;
+        fn get_smart_transfer_preauthorizations(
+            &self,
+            api_key: &str
+        ) -> Vec<SmartTransferPreauthorization>;
+        fn get_smart_transfer_preauthorization(
+            &self,
+            api_key: &str,
+            preauthorization_id: &str
+        ) -> SmartTransferPreauthorization;
+        fn create_smart_transfer_payment(
+            &self,
+            api_key: &str,
+            preauthorization_id: &str,
+            recipient_id: &str,
+            amount: &Money,
+            description: Option<&str>
+        ) -> SmartTransferPayment;
+        fn create_smart_transfer_payment_with_idempotency_key(
+            &self,
+            api_key: &str,
+            preauthorization_id: &str,
+            recipient_id: &str,
+            amount: &Money,
+            options: &CreateSmartTransferPaymentOptions<'_>,
+            idempotency_key: Option<&str>
+        ) -> SmartTransferPayment;
+        fn get_smart_transfer_payment(&self, api_key: &str, payment_id: &str) -> SmartTransferPayment;
+        fn create_boleto_connection(
+            &self,
+            api_key: &str,
+            connector_id: ConnectorId,
+            credentials: &HashMap<String, String>
+        ) -> BoletoConnection;
+        fn issue_boleto(
+            &self,
+            api_key: &str,
+            boleto_connection_id: &str,
+            boleto: &NewBoleto
+        ) -> Boleto;
+        fn get_boleto(&self, api_key: &str, boleto_id: &str) -> Boleto;
+        fn cancel_boleto(&self, api_key: &str, boleto_id: &str) -> Boleto;
+        fn create_boleto_payment_request(
+            &self,
+            api_key: &str,
+            digitable_line: &str,
+            description: Option<&str>
+        ) -> PaymentRequest;
+        fn create_boleto_payment_request_with_idempotency_key(
+            &self,
+            api_key: &str,
+            digitable_line: &str,
+            description: Option<&str>,
+            idempotency_key: Option<&str>
+        ) -> PaymentRequest;
+        fn get_consents(&self, api_key: &str, item_id: ItemId) -> Vec<Consent>;
+        fn get_consent(&self, api_key: &str, consent_id: &str) -> Consent;
+        fn revoke_consent(&self, api_key: &str, consent_id: &str) -> ();
+    }
+
+    blocking_streams! {
+        fn stream_connectors(&self, api_key: &'a str, filter: &'a ConnectorFilter) -> Connector;
+        fn stream_categories(&self, api_key: &'a str) -> Category;
+        fn stream_category_rules(&self, api_key: &'a str) -> CategoryRule;
+        fn stream_webhooks(&self, api_key: &'a str) -> Webhook;
+        fn stream_smart_transfer_preauthorizations(
+            &self,
+            api_key: &'a str
+        ) -> SmartTransferPreauthorization;
+        fn stream_consents(&self, api_key: &'a str, item_id: ItemId) -> Consent;
+    }
+}
+
+impl From<crate::Client> for Client {
+    fn from(client: crate::Client) -> Self {
+        Self::from_async(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockServer;
+
+    #[test]
+    fn can_call_mock_server_without_runtime() {
+        let server_runtime = Runtime::new().unwrap();
+        let server = server_runtime.block_on(MockServer::start());
+        let client = Client::from_async(server.client());
+
+        let api_key = client.create_api_key().unwrap();
+        let connectors = client.get_connectors(&api_key, true).unwrap();
+        assert!(connectors.iter().any(|c| c.name == "Pluggy Bank"));
+
+        let categories: Vec<_> = client
+            .stream_categories(&api_key, PageOptions::default().page_size(2))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            categories.len(),
+            client.get_categories(&api_key).unwrap().len()
+        );
+
+        let parameters = crate::sandbox::SandboxScenario::Success.parameters();
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &parameters)
+            .unwrap();
+        client.delete_item(&api_key, item.id).unwrap();
+        assert!(client.get_item(&api_key, item.id).is_err());
+    }
+}
@@ -0,0 +1,498 @@
+//! Record and replay of Pluggy API traffic.
+//!
+//! A `Client` built with a recording `Cassette` writes every request and
+//! response to a JSON file, with API keys, client secrets and item
+//! credentials scrubbed. A client built with a replaying cassette serves the
+//! recorded responses without touching the network.
+//!
+//! Credentials are recorded as `{{name}}` placeholders, named after their
+//! field. When replaying, placeholders in a response are filled back with the
+//! values sent in the request being served, so responses echoing the
+//! credentials replay as they were received.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use http::{HeaderMap, Method, Response, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::middleware::{Middleware, Next};
+use crate::transport::{HttpRequest, HttpResponse, TransportError, TransportFuture};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers never written to a cassette.
+const SECRET_HEADERS: &[&str] = &["x-api-key", "authorization", "cookie", "set-cookie"];
+/// Tokens issued by the API, recorded as `REDACTED` repeated to their length.
+const SECRET_TOKENS: &[&str] = &["apiKey", "accessToken"];
+/// JSON fields recorded as placeholders. Item parameters and boleto
+/// connection credentials are only secret in requests: in responses
+/// `credentials` describes the connector form.
+const SECRET_FIELDS: &[&str] = &["clientSecret", "password", "parameters"];
+const SECRET_REQUEST_FIELDS: &[&str] = &["credentials"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, without the API host.
+    pub uri: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+impl RecordedRequest {
+    /// Captures a request with its secrets scrubbed.
+    pub fn new(method: &Method, uri: &Uri, headers: &HeaderMap, body: &[u8]) -> Self {
+        Self {
+            method: method.to_string(),
+            uri: path_and_query(uri),
+            headers: scrub_headers(headers),
+            body: scrub_body(body, &request_secrets(uri), &mut BTreeMap::new()),
+        }
+    }
+}
+
+impl RecordedResponse {
+    /// Captures a response with its secrets scrubbed.
+    pub fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Self {
+        Self {
+            status: status.as_u16(),
+            headers: scrub_headers(headers),
+            body: scrub_body(
+                body,
+                &Secrets::fields(&[SECRET_FIELDS]),
+                &mut BTreeMap::new(),
+            ),
+        }
+    }
+}
+
+/// Which parts of a JSON body are secret.
+struct Secrets {
+    fields: Vec<&'static str>,
+    /// Every top-level value is secret, as in `/connectors/{id}/validate`,
+    /// which takes the item parameters as the body itself.
+    whole_body: bool,
+}
+
+impl Secrets {
+    fn fields(fields: &[&[&'static str]]) -> Self {
+        Self {
+            fields: fields.concat(),
+            whole_body: false,
+        }
+    }
+}
+
+fn request_secrets(uri: &Uri) -> Secrets {
+    let segments: Vec<&str> = uri.path().split('/').filter(|s| !s.is_empty()).collect();
+
+    Secrets {
+        whole_body: matches!(segments.as_slice(), ["connectors", _, "validate"]),
+        ..Secrets::fields(&[SECRET_FIELDS, SECRET_REQUEST_FIELDS])
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl Cassette {
+    /// Starts recording to `path`, replacing any previous recording.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Record,
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Loads the interactions recorded at `path`.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let file: CassetteFile = serde_json::from_slice(&fs::read(path.as_ref())?)?;
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Replay,
+            interactions: Mutex::new(
+                file.interactions
+                    .into_iter()
+                    .map(|interaction| (interaction, false))
+                    .collect(),
+            ),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    /// Appends an interaction, then rewrites the cassette file.
+    pub fn record_interaction(&self, interaction: Interaction) -> Result<(), TransportError> {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push((interaction, true));
+
+        let file = CassetteFile {
+            interactions: interactions
+                .iter()
+                .map(|(interaction, _)| interaction.clone())
+                .collect(),
+        };
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec_pretty(&file)?)?;
+
+        Ok(())
+    }
+
+    /// Serves the first recorded interaction not replayed yet with the same
+    /// method, path and query, filling its placeholders with the credentials
+    /// sent in `request`.
+    pub fn replay_interaction(
+        &self,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        let (method, uri) = (request.method(), path_and_query(request.uri()));
+        let mut credentials = BTreeMap::new();
+        scrub_body(
+            request.body(),
+            &request_secrets(request.uri()),
+            &mut credentials,
+        );
+        let mut interactions = self.interactions.lock().unwrap();
+        let (interaction, replayed) = interactions
+            .iter_mut()
+            .find(|(interaction, replayed)| {
+                !*replayed
+                    && interaction.request.method == method.as_str()
+                    && interaction.request.uri == uri
+            })
+            .ok_or_else(|| format!("No recorded interaction for {} {}", method, uri))?;
+        *replayed = true;
+
+        let mut response = Response::builder().status(interaction.response.status);
+        for (name, value) in &interaction.response.headers {
+            response = response.header(name.as_str(), value.as_str());
+        }
+        let body = match &interaction.response.body {
+            Some(Value::String(body)) => body.clone(),
+            Some(body) => {
+                let mut body = body.clone();
+                restore(&mut body, &credentials);
+                body.to_string()
+            }
+            None => String::new(),
+        };
+
+        Ok(response.body(body.into_bytes())?)
+    }
+}
+
+/// Records the requests going through the chain, or replays them without
+/// calling the rest of the chain.
+impl Middleware for Cassette {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        Box::pin(async move {
+            if self.mode() == CassetteMode::Replay {
+                return self.replay_interaction(&request);
+            }
+
+            let recorded_request = RecordedRequest::new(
+                request.method(),
+                request.uri(),
+                request.headers(),
+                request.body(),
+            );
+            let response = next.run(request).await?;
+
+            self.record_interaction(Interaction {
+                request: recorded_request,
+                response: RecordedResponse::new(
+                    response.status(),
+                    response.headers(),
+                    response.body(),
+                ),
+            })?;
+
+            Ok(response)
+        })
+    }
+}
+
+fn path_and_query(uri: &Uri) -> String {
+    uri.path_and_query()
+        .map(|path_and_query| path_and_query.to_string())
+        .unwrap_or_else(|| uri.path().to_string())
+}
+
+fn scrub_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED
+            } else {
+                value.to_str().ok()?
+            };
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Bodies that aren't JSON are kept as plain strings. The scrubbed
+/// credentials are collected in `credentials`, by placeholder.
+fn scrub_body(
+    body: &[u8],
+    secrets: &Secrets,
+    credentials: &mut BTreeMap<String, Value>,
+) -> Option<Value> {
+    if body.is_empty() {
+        return None;
+    }
+
+    match serde_json::from_slice(body) {
+        Ok(mut value) => {
+            match &mut value {
+                Value::Object(fields) if secrets.whole_body => {
+                    for (name, field) in fields.iter_mut() {
+                        redact(name, field, credentials);
+                    }
+                }
+                value => scrub_value(value, secrets, credentials),
+            }
+            Some(value)
+        }
+        Err(_) => Some(Value::String(String::from_utf8_lossy(body).into_owned())),
+    }
+}
+
+fn scrub_value(value: &mut Value, secrets: &Secrets, credentials: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if SECRET_TOKENS.contains(&name.as_str()) {
+                    if let Value::String(token) = field {
+                        *token = REDACTED.chars().cycle().take(token.len()).collect();
+                    }
+                } else if secrets.fields.contains(&name.as_str()) {
+                    redact(name, field, credentials);
+                } else {
+                    scrub_value(field, secrets, credentials);
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| scrub_value(value, secrets, credentials)),
+        _ => {}
+    }
+}
+
+/// Replaces every value with a placeholder named after its field, keeping
+/// the shape of objects so replayed payloads still deserialize.
+fn redact(name: &str, value: &mut Value, credentials: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => fields
+            .iter_mut()
+            .for_each(|(name, field)| redact(name, field, credentials)),
+        Value::Null => {}
+        _ => {
+            let placeholder = format!("{{{{{}}}}}", name);
+            credentials.insert(placeholder.clone(), value.take());
+            *value = Value::String(placeholder);
+        }
+    }
+}
+
+fn restore(value: &mut Value, credentials: &BTreeMap<String, Value>) {
+    match value {
+        Value::String(placeholder) => {
+            if let Some(credential) = credentials.get(placeholder.as_str()) {
+                *value = credential.clone();
+            }
+        }
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| restore(field, credentials)),
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| restore(value, credentials)),
+        _ => {}
+    }
+}
+
+#[cfg(all(test, feature = "hyper"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::testing::MockServer;
+    use crate::transport::HyperTransport;
+    use crate::{Client, ConnectorId};
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("pluggy-cassettes-{}", std::process::id()))
+            .join(format!("{}.json", name))
+    }
+
+    #[tokio::test]
+    async fn can_record_and_replay() {
+        let path = cassette_path("can_record_and_replay");
+        let parameters = HashMap::from([
+            ("user".to_string(), "user-ok".to_string()),
+            ("password".to_string(), "password-ok".to_string()),
+        ]);
+
+        let server = MockServer::start().await;
+        let client = Client::builder(
+            crate::testing::MOCK_CLIENT_ID.to_string(),
+            crate::testing::MOCK_CLIENT_SECRET.to_string(),
+        )
+        .base_url(server.url())
+        .transport(HyperTransport::http())
+        .cassette(Cassette::record(&path))
+        .build();
+        let api_key = client.create_api_key().await.unwrap();
+        let recorded_api_key_len = api_key.len();
+        let recorded = client
+            .create_item(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+        let connector = client
+            .get_connector(&api_key, ConnectorId(201))
+            .await
+            .unwrap();
+        drop(server);
+
+        let recording = fs::read_to_string(&path).unwrap();
+        assert!(!recording.contains(&api_key));
+        assert!(!recording.contains(crate::testing::MOCK_CLIENT_SECRET));
+        let recorded_parameters = &Cassette::replay(&path).unwrap().interactions()[1]
+            .request
+            .body
+            .clone()
+            .unwrap()["parameters"];
+        assert_eq!(recorded_parameters["password"], "{{password}}");
+
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .base_url("http://127.0.0.1:9")
+            .cassette(Cassette::replay(&path).unwrap())
+            .build();
+        let api_key = client.create_api_key().await.unwrap();
+        let replayed = client
+            .create_item(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+
+        assert!(REDACTED.starts_with(&api_key[..REDACTED.len()]));
+        assert_eq!(api_key.len(), recorded_api_key_len);
+        assert_eq!(replayed.id, recorded.id);
+        assert_eq!(replayed.connector.name, "Pluggy Bank");
+        assert_eq!(
+            client
+                .get_connector(&api_key, ConnectorId(201))
+                .await
+                .unwrap()
+                .name,
+            connector.name
+        );
+        assert!(client
+            .get_connector(&api_key, ConnectorId(201))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn can_replay_echoed_credentials() {
+        let path = cassette_path("can_replay_echoed_credentials");
+        let parameters = HashMap::from([("user", "user-ok"), ("password", "password-ok")]);
+
+        let server = MockServer::start().await;
+        let client = Client::builder(
+            crate::testing::MOCK_CLIENT_ID.to_string(),
+            crate::testing::MOCK_CLIENT_SECRET.to_string(),
+        )
+        .base_url(server.url())
+        .transport(HyperTransport::http())
+        .cassette(Cassette::record(&path))
+        .build();
+        let api_key = client.create_api_key().await.unwrap();
+        let connect_token = client.create_connect_token(&api_key).await.unwrap();
+        client
+            .validate_parameters(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+        drop(server);
+
+        let recording = fs::read_to_string(&path).unwrap();
+        assert!(!recording.contains(&connect_token));
+        assert!(!recording.contains("user-ok"));
+        assert!(!recording.contains("password-ok"));
+        let validation = &Cassette::replay(&path).unwrap().interactions()[2];
+        assert_eq!(
+            validation.request.body.as_ref().unwrap()["user"],
+            "{{user}}"
+        );
+
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .base_url("http://127.0.0.1:9")
+            .cassette(Cassette::replay(&path).unwrap())
+            .build();
+        let api_key = client.create_api_key().await.unwrap();
+        let replayed_token = client.create_connect_token(&api_key).await.unwrap();
+        let parameters = HashMap::from([("user", "user-2"), ("password", "secret-2")]);
+        let result = client
+            .validate_parameters(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+
+        assert_eq!(replayed_token.len(), connect_token.len());
+        assert_eq!(result.parameters["user"], "user-2");
+        assert_eq!(result.parameters["password"], "secret-2");
+    }
+}
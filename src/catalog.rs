@@ -0,0 +1,502 @@
+//! Connector list cached in memory.
+//!
+//! `ConnectorCatalog` fetches the connectors once and serves them indexed by
+//! id, name, country and type, fetching them again once they are older than
+//! its refresh interval:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! use pluggy::catalog::ConnectorCatalog;
+//! use pluggy::{Client, Country};
+//!
+//! let (client, api_key) = Client::new_from_env_with_api_key().await?;
+//! let catalog = Arc::new(ConnectorCatalog::new(Duration::from_secs(5 * 60)));
+//! let mut events = catalog.subscribe();
+//!
+//! let connectors = catalog.connectors(&client, &api_key).await?;
+//! let brazilian = connectors.by_country(&Country::BR);
+//!
+//! tokio::spawn({
+//!     let catalog = catalog.clone();
+//!     async move { catalog.watch(&client, &api_key).await }
+//! });
+//! while let Ok(event) = events.recv().await {
+//!     if event.is_degraded() {
+//!         println!("{} is down", event.connector().name);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The API doesn't send an `ETag` for `/connectors`, so every refresh fetches
+//! the whole list and diffs it with the previous one. Connectors that were
+//! added, removed or changed health status are sent to subscribers as
+//! `CatalogEvent`s; the first load sends none.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::Instant;
+
+use crate::pagination::PageOptions;
+use crate::transport::TransportError;
+use crate::{
+    Client, Connector, ConnectorFilter, ConnectorId, ConnectorStatus, ConnectorType, Country,
+};
+
+/// Events kept for subscribers that fall behind before the oldest is dropped.
+const EVENT_CAPACITY: usize = 64;
+
+/// Connectors indexed by id, name, country and type.
+#[derive(Debug, Default)]
+pub struct ConnectorIndex {
+    connectors: BTreeMap<ConnectorId, Connector>,
+    by_name: HashMap<String, ConnectorId>,
+    by_country: HashMap<Country, Vec<ConnectorId>>,
+    by_type: HashMap<ConnectorType, Vec<ConnectorId>>,
+}
+
+impl ConnectorIndex {
+    pub fn new(connectors: Vec<Connector>) -> Self {
+        connectors.into_iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.connectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connectors.is_empty()
+    }
+
+    pub fn get(&self, connector_id: ConnectorId) -> Option<&Connector> {
+        self.connectors.get(&connector_id)
+    }
+
+    /// Connector with the given name, ignoring case. When several share a
+    /// name, the one with the lowest id.
+    pub fn by_name(&self, name: &str) -> Option<&Connector> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .and_then(|connector_id| self.get(*connector_id))
+    }
+
+    pub fn by_country(&self, country: &Country) -> Vec<&Connector> {
+        self.lookup(self.by_country.get(country))
+    }
+
+    pub fn by_type(&self, connector_type: &ConnectorType) -> Vec<&Connector> {
+        self.lookup(self.by_type.get(connector_type))
+    }
+
+    /// Connectors in id order.
+    pub fn iter(&self) -> impl Iterator<Item = &Connector> {
+        self.connectors.values()
+    }
+
+    /// What changed from this list to `newer`.
+    pub fn diff(&self, newer: &ConnectorIndex) -> Vec<CatalogEvent> {
+        let mut events = Vec::new();
+
+        for connector in newer.iter() {
+            match self.get(connector.id) {
+                None => events.push(CatalogEvent::Added(connector.clone())),
+                Some(previous) if health_status(previous) != health_status(connector) => events
+                    .push(CatalogEvent::HealthChanged {
+                        connector: connector.clone(),
+                        previous: health_status(previous).cloned(),
+                        current: health_status(connector).cloned(),
+                    }),
+                Some(_) => {}
+            }
+        }
+
+        events.extend(
+            self.iter()
+                .filter(|connector| newer.get(connector.id).is_none())
+                .map(|connector| CatalogEvent::Removed(connector.clone())),
+        );
+
+        events
+    }
+
+    fn lookup(&self, connector_ids: Option<&Vec<ConnectorId>>) -> Vec<&Connector> {
+        connector_ids
+            .into_iter()
+            .flatten()
+            .filter_map(|connector_id| self.get(*connector_id))
+            .collect()
+    }
+}
+
+impl FromIterator<Connector> for ConnectorIndex {
+    fn from_iter<I: IntoIterator<Item = Connector>>(iter: I) -> Self {
+        let connectors: BTreeMap<_, _> = iter
+            .into_iter()
+            .map(|connector| (connector.id, connector))
+            .collect();
+
+        let mut index = Self::default();
+        for connector in connectors.values() {
+            index
+                .by_name
+                .entry(connector.name.to_lowercase())
+                .or_insert(connector.id);
+            index
+                .by_country
+                .entry(connector.country.clone())
+                .or_default()
+                .push(connector.id);
+            index
+                .by_type
+                .entry(connector.connector_type.clone())
+                .or_default()
+                .push(connector.id);
+        }
+        index.connectors = connectors;
+
+        index
+    }
+}
+
+fn health_status(connector: &Connector) -> Option<&ConnectorStatus> {
+    connector.health.as_ref().map(|health| &health.status)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogEvent {
+    Added(Connector),
+    Removed(Connector),
+    /// The statuses are `None` for connectors without health information.
+    HealthChanged {
+        connector: Connector,
+        previous: Option<ConnectorStatus>,
+        current: Option<ConnectorStatus>,
+    },
+}
+
+impl CatalogEvent {
+    pub fn connector(&self) -> &Connector {
+        match self {
+            Self::Added(connector) | Self::Removed(connector) => connector,
+            Self::HealthChanged { connector, .. } => connector,
+        }
+    }
+
+    /// Whether an online connector became unstable or offline.
+    pub fn is_degraded(&self) -> bool {
+        matches!(
+            self,
+            Self::HealthChanged {
+                previous: Some(ConnectorStatus::Online),
+                current: Some(ConnectorStatus::Unstable | ConnectorStatus::Offline),
+                ..
+            }
+        )
+    }
+}
+
+struct Loaded {
+    index: Arc<ConnectorIndex>,
+    loaded_at: Instant,
+}
+
+pub struct ConnectorCatalog {
+    filter: ConnectorFilter,
+    refresh_interval: Duration,
+    loaded: RwLock<Option<Loaded>>,
+    /// Held while fetching, so concurrent callers of a stale catalog wait for
+    /// a single request.
+    refreshing: Mutex<()>,
+    events: broadcast::Sender<CatalogEvent>,
+}
+
+impl ConnectorCatalog {
+    /// Catalog of the non-sandbox connectors, fetched again once they are
+    /// older than `refresh_interval`.
+    pub fn new(refresh_interval: Duration) -> Self {
+        Self {
+            filter: ConnectorFilter::default(),
+            refresh_interval,
+            loaded: RwLock::new(None),
+            refreshing: Mutex::new(()),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Catalogs only the connectors matching `filter`.
+    pub fn filter(mut self, filter: ConnectorFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Receives the events of every refresh after this call. Subscribers
+    /// that fall more than 64 events behind miss the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<CatalogEvent> {
+        self.events.subscribe()
+    }
+
+    /// The connectors loaded last, however old, without fetching them.
+    pub fn cached(&self) -> Option<Arc<ConnectorIndex>> {
+        self.loaded
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|loaded| loaded.index.clone())
+    }
+
+    /// The cached connectors, fetched first if they were never loaded or are
+    /// older than the refresh interval.
+    pub async fn connectors(
+        &self,
+        client: &Client,
+        api_key: &str,
+    ) -> Result<Arc<ConnectorIndex>, Box<dyn std::error::Error>> {
+        if let Some(index) = self.fresh() {
+            return Ok(index);
+        }
+
+        let _refreshing = self.refreshing.lock().await;
+        if let Some(index) = self.fresh() {
+            return Ok(index);
+        }
+        let (index, _) = self
+            .load(client, api_key)
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)?;
+
+        Ok(index)
+    }
+
+    /// Fetches the connectors now and returns what changed since they were
+    /// last loaded.
+    pub async fn refresh(
+        &self,
+        client: &Client,
+        api_key: &str,
+    ) -> Result<Vec<CatalogEvent>, Box<dyn std::error::Error>> {
+        self.reload(client, api_key)
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)
+    }
+
+    /// Refreshes the catalog every refresh interval, starting right away,
+    /// until a refresh fails. Requests are retried by the client's retry
+    /// policy before a refresh fails. Panics if the refresh interval is zero.
+    ///
+    /// Meant to be spawned next to the code subscribed to the catalog.
+    pub async fn watch(
+        &self,
+        client: &Client,
+        api_key: &str,
+    ) -> Box<dyn std::error::Error + Send + Sync> {
+        let mut interval = tokio::time::interval(self.refresh_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            if let Err(error) = self.reload(client, api_key).await {
+                return error;
+            }
+        }
+    }
+
+    /// `refresh` failing with an error that can be sent across threads.
+    async fn reload(
+        &self,
+        client: &Client,
+        api_key: &str,
+    ) -> Result<Vec<CatalogEvent>, TransportError> {
+        let _refreshing = self.refreshing.lock().await;
+        let (_, events) = self.load(client, api_key).await?;
+
+        Ok(events)
+    }
+
+    fn fresh(&self) -> Option<Arc<ConnectorIndex>> {
+        self.loaded
+            .read()
+            .unwrap()
+            .as_ref()
+            .filter(|loaded| loaded.loaded_at.elapsed() < self.refresh_interval)
+            .map(|loaded| loaded.index.clone())
+    }
+
+    async fn load(
+        &self,
+        client: &Client,
+        api_key: &str,
+    ) -> Result<(Arc<ConnectorIndex>, Vec<CatalogEvent>), TransportError> {
+        let pages = client.connector_pages(api_key, &self.filter, PageOptions::default())?;
+        let connectors = pages.collect_all_send().await?;
+        let index = Arc::new(ConnectorIndex::new(connectors));
+
+        let previous = self.loaded.write().unwrap().replace(Loaded {
+            index: index.clone(),
+            loaded_at: Instant::now(),
+        });
+        let events = previous
+            .map(|previous| previous.index.diff(&index))
+            .unwrap_or_default();
+
+        for event in &events {
+            // Fails only when nobody is subscribed.
+            self.events.send(event.clone()).ok();
+        }
+
+        Ok((index, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "hyper")]
+    use crate::testing::MockServer;
+    use crate::{ConnectorHealth, Item};
+
+    fn connector(id: i32, name: &str, country: Country) -> Connector {
+        let item: Item =
+            serde_json::from_str(include_str!("resources/fixtures/item.json")).unwrap();
+        Connector {
+            id: ConnectorId(id),
+            name: name.to_string(),
+            country,
+            health: Some(ConnectorHealth {
+                status: ConnectorStatus::Online,
+                stage: None,
+            }),
+            ..item.connector
+        }
+    }
+
+    #[test]
+    fn can_index_connectors() {
+        let index = ConnectorIndex::new(vec![
+            connector(3, "Nubank", Country::BR),
+            connector(1, "Banco Galicia", Country::AR),
+            connector(2, "Nubank", Country::MX),
+        ]);
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.by_name("NUBANK").unwrap().id, ConnectorId(2));
+        assert_eq!(index.by_country(&Country::BR)[0].id, ConnectorId(3));
+        assert!(index
+            .by_country(&Country::from("CO".to_string()))
+            .is_empty());
+        assert_eq!(
+            index
+                .by_type(&index.get(ConnectorId(1)).unwrap().connector_type)
+                .len(),
+            3
+        );
+        let ids: Vec<_> = index.iter().map(|connector| connector.id.0).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn can_diff_connector_lists() {
+        let online = connector(1, "Itaú", Country::BR);
+        let offline = Connector {
+            health: Some(ConnectorHealth {
+                status: ConnectorStatus::Offline,
+                stage: None,
+            }),
+            ..online.clone()
+        };
+        let removed = connector(2, "Bradesco", Country::BR);
+        let added = connector(3, "Nubank", Country::BR);
+
+        let before = ConnectorIndex::new(vec![online, removed.clone()]);
+        let after = ConnectorIndex::new(vec![offline.clone(), added.clone()]);
+        let events = before.diff(&after);
+
+        assert_eq!(
+            events,
+            vec![
+                CatalogEvent::HealthChanged {
+                    connector: offline,
+                    previous: Some(ConnectorStatus::Online),
+                    current: Some(ConnectorStatus::Offline),
+                },
+                CatalogEvent::Added(added),
+                CatalogEvent::Removed(removed),
+            ]
+        );
+        assert!(events[0].is_degraded());
+        assert!(!events[1].is_degraded());
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn can_refresh_and_notify_subscribers() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let catalog = ConnectorCatalog::new(Duration::from_secs(60 * 60)).filter(ConnectorFilter {
+            sandbox: true,
+            ..Default::default()
+        });
+        let mut events = catalog.subscribe();
+
+        assert!(catalog.cached().is_none());
+        let connectors = catalog.connectors(&client, &api_key).await.unwrap();
+        assert_eq!(connectors.len(), 2);
+        assert!(Arc::ptr_eq(
+            &connectors,
+            &catalog.connectors(&client, &api_key).await.unwrap()
+        ));
+
+        server.set_connector_health(ConnectorId(201), ConnectorStatus::Unstable);
+        let mut new_connector = serde_json::to_value(connectors.get(ConnectorId(201))).unwrap();
+        new_connector["id"] = 612.into();
+        new_connector["name"] = "Nubank".into();
+        server.add_connector(new_connector, false);
+
+        let changes = catalog.refresh(&client, &api_key).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].is_degraded());
+        assert_eq!(changes[0].connector().id, ConnectorId(201));
+        assert!(
+            matches!(&changes[1], CatalogEvent::Added(connector) if connector.name == "Nubank")
+        );
+        assert_eq!(events.recv().await.unwrap(), changes[0]);
+        assert_eq!(events.recv().await.unwrap(), changes[1]);
+
+        let connectors = catalog.cached().unwrap();
+        assert_eq!(connectors.by_name("nubank").unwrap().id, ConnectorId(612));
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test(start_paused = true)]
+    async fn can_watch_for_health_changes() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let catalog = Arc::new(ConnectorCatalog::new(Duration::from_secs(60)));
+        let mut events = catalog.subscribe();
+
+        let watch = tokio::spawn({
+            let catalog = catalog.clone();
+            async move { catalog.watch(&client, &api_key).await }
+        });
+        while catalog.cached().is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        server.set_connector_health(ConnectorId(201), ConnectorStatus::Offline);
+        let event = tokio::time::timeout(Duration::from_secs(2 * 60), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(event.is_degraded());
+        assert_eq!(event.connector().id, ConnectorId(201));
+        watch.abort();
+    }
+}
@@ -0,0 +1,273 @@
+//! What to do about an item's execution error.
+//!
+//! `Guidance` maps an `ExecutionErrorCodes` to the `RecoveryAction` that
+//! solves it and to a message that can be shown to the end user, in
+//! Portuguese, Spanish or English:
+//!
+//! ```
+//! # fn show(item: &pluggy::Item) {
+//! use pluggy::guidance::{Guidance, Language, RecoveryAction};
+//!
+//! if let Some(guidance) = Guidance::for_item(item) {
+//!     println!("{}", guidance.message(Language::PtBr));
+//!     if let RecoveryAction::VisitInstitution { url } = &guidance.action {
+//!         println!("{}", url);
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! The error's own `message` is meant for developers and is in English.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Connector, ExecutionErrorCodes, Item};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    PtBr,
+    Es,
+    En,
+}
+
+/// Error for a language tag with no translation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedLanguage(pub String);
+
+impl fmt::Display for UnsupportedLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no messages in language {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedLanguage {}
+
+impl FromStr for Language {
+    type Err = UnsupportedLanguage;
+
+    /// Parses a language tag such as `pt-BR`, `es-AR` or `en`. Regions other
+    /// than Brazil's get the same Portuguese messages.
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "pt" => Ok(Self::PtBr),
+            "es" => Ok(Self::Es),
+            "en" => Ok(Self::En),
+            _ => Err(UnsupportedLanguage(tag.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// Ask the user for their credentials, or a new MFA token, again and
+    /// update the item with them.
+    UpdateCredentials,
+    /// Update the item later without changing anything: the error is on the
+    /// institution's side or temporary.
+    RetryLater,
+    /// Send the user to the institution to solve the problem there, then
+    /// update the item. `url` is the connector's `reset_password_url`, or its
+    /// `institution_url` when it has none.
+    VisitInstitution { url: String },
+    /// Keep polling the item while the user authorizes the access on the
+    /// institution's app.
+    WaitForAuthorization,
+    /// Ask the user to log out of their other session with the institution,
+    /// then update the item.
+    EndOtherSession,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guidance {
+    pub code: ExecutionErrorCodes,
+    pub action: RecoveryAction,
+}
+
+impl Guidance {
+    pub fn new(code: &ExecutionErrorCodes, connector: &Connector) -> Self {
+        let action = match code {
+            ExecutionErrorCodes::InvalidCredentials
+            | ExecutionErrorCodes::InvalidCredentialsMfa
+            | ExecutionErrorCodes::UserInputTimeout
+            | ExecutionErrorCodes::UserAuthorizationNotGranted => RecoveryAction::UpdateCredentials,
+            ExecutionErrorCodes::AccountLocked
+            | ExecutionErrorCodes::AccountCredentialsReset
+            | ExecutionErrorCodes::AccountNeedsAction => RecoveryAction::VisitInstitution {
+                url: connector
+                    .reset_password_url
+                    .clone()
+                    .unwrap_or_else(|| connector.institution_url.clone()),
+            },
+            ExecutionErrorCodes::UserAuthorizationPending => RecoveryAction::WaitForAuthorization,
+            ExecutionErrorCodes::AlreadyLoggedIn => RecoveryAction::EndOtherSession,
+            ExecutionErrorCodes::SiteNotAvailable
+            | ExecutionErrorCodes::ConnectionError
+            | ExecutionErrorCodes::UnexpectedError
+            | ExecutionErrorCodes::Unknown(_) => RecoveryAction::RetryLater,
+        };
+
+        Self {
+            code: code.clone(),
+            action,
+        }
+    }
+
+    /// Guidance for the item's error, if it has one.
+    pub fn for_item(item: &Item) -> Option<Self> {
+        item.error
+            .as_ref()
+            .map(|error| Self::new(&error.code, &item.connector))
+    }
+
+    /// Message explaining the error to the end user and what they can do.
+    pub fn message(&self, language: Language) -> &'static str {
+        let [pt_br, es, en] = messages(&self.code);
+        match language {
+            Language::PtBr => pt_br,
+            Language::Es => es,
+            Language::En => en,
+        }
+    }
+}
+
+fn messages(code: &ExecutionErrorCodes) -> [&'static str; 3] {
+    match code {
+        ExecutionErrorCodes::InvalidCredentials => [
+            "As credenciais informadas estão incorretas. Confira e tente novamente.",
+            "Las credenciales ingresadas son incorrectas. Revísalas e inténtalo de nuevo.",
+            "The credentials you entered are incorrect. Please check them and try again.",
+        ],
+        ExecutionErrorCodes::InvalidCredentialsMfa => [
+            "O código de verificação está incorreto ou expirou. Tente novamente com um novo código.",
+            "El código de verificación es incorrecto o expiró. Inténtalo de nuevo con un código nuevo.",
+            "The verification code is incorrect or expired. Please try again with a new code.",
+        ],
+        ExecutionErrorCodes::UserInputTimeout => [
+            "O código de verificação não foi informado a tempo. Tente novamente.",
+            "El código de verificación no se ingresó a tiempo. Inténtalo de nuevo.",
+            "The verification code wasn't entered in time. Please try again.",
+        ],
+        ExecutionErrorCodes::AlreadyLoggedIn => [
+            "Sua conta já está aberta em outra sessão. Saia dela e tente novamente em alguns minutos.",
+            "Tu cuenta ya está abierta en otra sesión. Ciérrala e inténtalo de nuevo en unos minutos.",
+            "Your account is already open in another session. Log out of it and try again in a few minutes.",
+        ],
+        ExecutionErrorCodes::AccountLocked => [
+            "Sua conta está bloqueada. Desbloqueie-a no site ou aplicativo do seu banco e tente novamente.",
+            "Tu cuenta está bloqueada. Desbloquéala en el sitio o la aplicación de tu banco e inténtalo de nuevo.",
+            "Your account is locked. Unlock it on your bank's website or app and try again.",
+        ],
+        ExecutionErrorCodes::AccountCredentialsReset => [
+            "Seu banco pede que você redefina sua senha. Crie uma nova no site ou aplicativo do banco e conecte novamente.",
+            "Tu banco te pide restablecer tu contraseña. Crea una nueva en el sitio o la aplicación de tu banco y vuelve a conectarte.",
+            "Your bank asks you to reset your password. Create a new one on your bank's website or app, then connect again.",
+        ],
+        ExecutionErrorCodes::AccountNeedsAction => [
+            "Seu banco precisa que você realize uma ação, como aceitar novos termos. Acesse o site ou aplicativo do banco e tente novamente.",
+            "Tu banco necesita que realices una acción, como aceptar nuevos términos. Ingresa al sitio o la aplicación de tu banco e inténtalo de nuevo.",
+            "Your bank needs you to take an action, such as accepting new terms. Go to your bank's website or app and try again.",
+        ],
+        ExecutionErrorCodes::UserAuthorizationPending => [
+            "Aguardando você autorizar o acesso no aplicativo do seu banco.",
+            "Esperando que autorices el acceso en la aplicación de tu banco.",
+            "Waiting for you to authorize the access on your bank's app.",
+        ],
+        ExecutionErrorCodes::UserAuthorizationNotGranted => [
+            "O acesso não foi autorizado no aplicativo do seu banco. Conecte novamente e aprove o acesso para continuar.",
+            "El acceso no fue autorizado en la aplicación de tu banco. Vuelve a conectarte y apruébalo para continuar.",
+            "The access wasn't authorized on your bank's app. Connect again and approve it to continue.",
+        ],
+        ExecutionErrorCodes::SiteNotAvailable => [
+            "O site do seu banco está indisponível no momento. Tente novamente mais tarde.",
+            "El sitio de tu banco no está disponible en este momento. Inténtalo de nuevo más tarde.",
+            "Your bank's website is unavailable right now. Please try again later.",
+        ],
+        ExecutionErrorCodes::ConnectionError => [
+            "Não foi possível conectar ao seu banco. Tente novamente mais tarde.",
+            "No pudimos conectarnos con tu banco. Inténtalo de nuevo más tarde.",
+            "We couldn't connect to your bank. Please try again later.",
+        ],
+        ExecutionErrorCodes::UnexpectedError | ExecutionErrorCodes::Unknown(_) => [
+            "Algo deu errado ao conectar ao seu banco. Tente novamente mais tarde.",
+            "Algo salió mal al conectarnos con tu banco. Inténtalo de nuevo más tarde.",
+            "Something went wrong while connecting to your bank. Please try again later.",
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> Item {
+        serde_json::from_str(include_str!("resources/fixtures/item.json")).unwrap()
+    }
+
+    #[test]
+    fn can_guide_every_error() {
+        let connector = item().connector;
+        for code in ExecutionErrorCodes::KNOWN {
+            let guidance = Guidance::new(code, &connector);
+            let asks_user = !matches!(guidance.action, RecoveryAction::RetryLater);
+            assert!(
+                asks_user == code.requires_user_action(),
+                "{} is guided to {:?}",
+                code,
+                guidance.action
+            );
+
+            let messages =
+                [Language::PtBr, Language::Es, Language::En].map(|l| guidance.message(l));
+            assert!(messages.iter().all(|message| !message.is_empty()));
+            assert!(messages[0] != messages[1] && messages[1] != messages[2]);
+        }
+    }
+
+    #[test]
+    fn can_send_user_to_institution() {
+        let mut item = item();
+        item.error.as_mut().unwrap().code = ExecutionErrorCodes::AccountLocked;
+        item.connector.reset_password_url = Some("https://bank.example/reset".to_string());
+        assert_eq!(
+            Guidance::for_item(&item).unwrap().action,
+            RecoveryAction::VisitInstitution {
+                url: "https://bank.example/reset".to_string()
+            }
+        );
+
+        item.connector.reset_password_url = None;
+        assert_eq!(
+            Guidance::for_item(&item).unwrap().action,
+            RecoveryAction::VisitInstitution {
+                url: item.connector.institution_url.clone()
+            }
+        );
+
+        item.error = None;
+        assert_eq!(Guidance::for_item(&item), None);
+    }
+
+    #[test]
+    fn can_parse_language_tags() {
+        assert_eq!("pt-BR".parse(), Ok(Language::PtBr));
+        assert_eq!("es_AR".parse(), Ok(Language::Es));
+        assert_eq!("EN".parse(), Ok(Language::En));
+        assert_eq!(
+            "fr".parse::<Language>(),
+            Err(UnsupportedLanguage("fr".to_string()))
+        );
+
+        let unknown = Guidance::new(
+            &ExecutionErrorCodes::from("NEW_ERROR".to_string()),
+            &item().connector,
+        );
+        assert_eq!(unknown.action, RecoveryAction::RetryLater);
+        assert_eq!(
+            unknown.message(Language::En),
+            Guidance::new(&ExecutionErrorCodes::UnexpectedError, &item().connector)
+                .message(Language::En)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! `tracing` spans around API calls, enabled with the `tracing` feature.
+//!
+//! Every request runs in a `pluggy.request` span recording its endpoint,
+//! method, status, latency, retry count and Pluggy request id. With the
+//! `DEBUG` level enabled, request and response headers and bodies are logged
+//! as well, scrubbed like cassette recordings: API keys, client secrets and
+//! every value of item parameters, boleto connection credentials and
+//! `/connectors/{id}/validate` bodies never reach the subscriber.
+
+use std::time::Instant;
+
+use tracing::field::{self, Empty};
+use tracing::{Instrument, Level, Span};
+
+use crate::cassette::{RecordedRequest, RecordedResponse};
+use crate::middleware::{Middleware, Next};
+use crate::transport::{endpoint, HttpRequest, TransportFuture};
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Middleware running requests inside a `pluggy.request` span.
+#[derive(Debug, Clone, Default)]
+pub struct TracingMiddleware;
+
+impl Middleware for TracingMiddleware {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        let span = tracing::info_span!(
+            "pluggy.request",
+            endpoint = %endpoint(request.uri().path()),
+            method = %request.method(),
+            status = Empty,
+            latency_ms = Empty,
+            retries = 0,
+            request_id = Empty,
+        );
+
+        Box::pin(
+            async move {
+                if tracing::enabled!(Level::DEBUG) {
+                    let recorded = RecordedRequest::new(
+                        request.method(),
+                        request.uri(),
+                        request.headers(),
+                        request.body(),
+                    );
+                    tracing::debug!(
+                        uri = %recorded.uri,
+                        headers = ?recorded.headers,
+                        body = %recorded.body.unwrap_or_default(),
+                        "pluggy request"
+                    );
+                }
+
+                let started_at = Instant::now();
+                let result = next.run(request).await;
+                let span = Span::current();
+                span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+
+                match &result {
+                    Ok(response) => {
+                        span.record("status", response.status().as_u16());
+                        if let Some(request_id) = response
+                            .headers()
+                            .get(REQUEST_ID_HEADER)
+                            .and_then(|value| value.to_str().ok())
+                        {
+                            span.record("request_id", field::display(request_id));
+                        }
+
+                        if tracing::enabled!(Level::DEBUG) {
+                            let recorded = RecordedResponse::new(
+                                response.status(),
+                                response.headers(),
+                                response.body(),
+                            );
+                            tracing::debug!(
+                                headers = ?recorded.headers,
+                                body = %recorded.body.unwrap_or_default(),
+                                "pluggy response"
+                            );
+                        }
+                    }
+                    Err(error) => tracing::warn!(error = %error, "pluggy request failed"),
+                }
+
+                result
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use http::StatusCode;
+    use serde_json::json;
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    use super::*;
+    use crate::transport::{json_response, MemoryTransport};
+    use crate::{Client, ConnectorId};
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Output {
+        type Writer = Output;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn can_trace_requests_without_secrets() {
+        let output = Output::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::DEBUG)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .with_writer(output.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let client = Client::builder("id".to_string(), "very-secret".to_string())
+            .transport(MemoryTransport::new(|_| {
+                let mut response =
+                    json_response(StatusCode::OK, &json!({ "apiKey": "secret-api-key" }));
+                response
+                    .headers_mut()
+                    .insert(REQUEST_ID_HEADER, "request-1".parse().unwrap());
+                response
+            }))
+            .build();
+        let api_key = client.create_api_key().await.unwrap();
+        let _ = client.get_connector(&api_key, ConnectorId(201)).await;
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("endpoint=/auth"));
+        assert!(output.contains("endpoint=/connectors/{id}"));
+        assert!(output.contains("status=200"));
+        assert!(output.contains("request_id=request-1"));
+        assert!(output.contains("[REDACTED]"));
+        assert!(!output.contains("very-secret"));
+        assert!(!output.contains("secret-api-key"));
+    }
+
+    #[tokio::test]
+    async fn can_trace_requests_without_credentials() {
+        let output = Output::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(output.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .transport(MemoryTransport::new(|_| {
+                json_response(StatusCode::OK, &json!({}))
+            }))
+            .build();
+        let parameters = HashMap::from([("user", "maria.souza"), ("cpf", "52998224725")]);
+        let _ = client
+            .validate_parameters("key", ConnectorId(2), &parameters)
+            .await;
+        let parameters = HashMap::from([("agency".to_string(), "0001-9".to_string())]);
+        let _ = client.create_item("key", ConnectorId(2), &parameters).await;
+        let credentials = HashMap::from([("clientId".to_string(), "inter-client".to_string())]);
+        let _ = client
+            .create_boleto_connection("key", ConnectorId(2), &credentials)
+            .await;
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("{{user}}"));
+        for credential in ["maria.souza", "52998224725", "0001-9", "inter-client"] {
+            assert!(!output.contains(credential), "{} was logged", credential);
+        }
+    }
+}
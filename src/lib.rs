@@ -1,21 +1,54 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use hyper::http::request::Builder;
-use hyper::{Body, Client as HyperClient, Method, Request, StatusCode};
-use hyper_tls::HttpsConnector;
+use http::request::Builder;
+use http::{Method, Request, StatusCode};
 use url::Url;
 
 use crate::auth::*;
-use crate::resources::*;
+use crate::cassette::Cassette;
+use crate::limit::{LimitMetrics, LimitMetricsSnapshot, Limiter, RateLimit};
+use crate::middleware::{Middleware, Next};
+use crate::pagination::{PageOptions, Paginated};
+pub use crate::resources::*;
+use crate::retry::{RetryPolicy, IDEMPOTENCY_KEY_HEADER};
+use crate::telemetry::{ItemReporter, MetricsHook, MetricsMiddleware};
+use crate::transport::{
+    default_transport, HttpRequest, HttpResponse, HttpTransport, TransportError, TransportFuture,
+};
+pub use rust_decimal::Decimal;
+pub use uuid::Uuid;
 
 mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cassette;
+pub mod catalog;
+pub mod guidance;
+#[cfg(feature = "tracing")]
+pub mod instrument;
+pub mod limit;
+pub mod middleware;
+pub mod pagination;
 mod resources;
+pub mod retry;
+#[cfg(all(any(test, feature = "test-util"), feature = "hyper"))]
+pub mod sandbox;
+pub mod telemetry;
+#[cfg(all(any(test, feature = "test-util"), feature = "hyper"))]
+pub mod testing;
+pub mod transport;
+
+const DEFAULT_URL: &str = "https://api.pluggy.ai";
 
 pub struct Client {
     client_id: String,
     client_secret: String,
     url: String,
-    client: HyperClient<HttpsConnector<hyper::client::connect::HttpConnector>>,
+    transport: Arc<dyn HttpTransport>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    limit_metrics: Arc<LimitMetrics>,
+    item_reporter: Option<ItemReporter>,
 }
 
 pub fn authenticated_request_builder(method: Method, url: &Url, api_key: &str) -> Builder {
@@ -27,18 +60,161 @@ pub fn authenticated_request_builder(method: Method, url: &Url, api_key: &str) -
         .header("X-API-KEY", api_key)
 }
 
+fn with_idempotency_key(builder: Builder, idempotency_key: Option<&str>) -> Builder {
+    match idempotency_key {
+        Some(idempotency_key) => builder.header(IDEMPOTENCY_KEY_HEADER, idempotency_key),
+        None => builder,
+    }
+}
+
+pub struct ClientBuilder {
+    client_id: String,
+    client_secret: String,
+    url: String,
+    transport: Option<Arc<dyn HttpTransport>>,
+    cassette: Option<Cassette>,
+    retry_policy: RetryPolicy,
+    rate_limit: Option<RateLimit>,
+    max_in_flight: Option<usize>,
+    metrics_hook: Option<Arc<dyn MetricsHook>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl ClientBuilder {
+    /// Points the client to another Pluggy API, such as a `testing::MockServer`.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sends requests through `transport` instead of `transport::default_transport()`.
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Records the traffic to, or replays it from, a cassette file.
+    pub fn cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Retries failed requests following `policy`. Requests are sent once by
+    /// default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Spaces requests, retries included, to stay under `limit`.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Sends at most `max` requests at once; the rest wait in line.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Reports requests and items to `hook`, such as
+    /// `telemetry::MetricsRecorder`.
+    pub fn metrics_hook(mut self, hook: impl MetricsHook + 'static) -> Self {
+        self.metrics_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Adds `middleware` to the chain requests go through, after the ones
+    /// added before it.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Chains tracing, metrics, the added middleware, retries, limits and
+    /// the cassette, in that order.
+    pub fn build(self) -> Client {
+        let mut middlewares: Vec<Arc<dyn Middleware>> = Vec::new();
+        #[cfg(feature = "tracing")]
+        middlewares.push(Arc::new(instrument::TracingMiddleware));
+        if let Some(hook) = &self.metrics_hook {
+            middlewares.push(Arc::new(MetricsMiddleware::new(hook.clone())));
+        }
+        middlewares.extend(self.middlewares);
+        if self.retry_policy.max_attempts() > 1 {
+            middlewares.push(Arc::new(self.retry_policy));
+        }
+        let limit_metrics = Arc::new(LimitMetrics::default());
+        if self.rate_limit.is_some() || self.max_in_flight.is_some() {
+            middlewares.push(Arc::new(Limiter::new(
+                self.rate_limit,
+                self.max_in_flight,
+                limit_metrics.clone(),
+            )));
+        }
+        if let Some(cassette) = self.cassette {
+            middlewares.push(Arc::new(cassette));
+        }
+
+        Client {
+            client_id: self.client_id,
+            client_secret: self.client_secret,
+            url: self.url,
+            transport: self.transport.unwrap_or_else(default_transport),
+            middlewares,
+            limit_metrics,
+            item_reporter: self.metrics_hook.map(ItemReporter::new),
+        }
+    }
+}
+
 impl Client {
     pub fn new(client_id: String, client_secret: String) -> Self {
-        let client = HyperClient::builder().build::<_, hyper::Body>(HttpsConnector::new());
+        Self::builder(client_id, client_secret).build()
+    }
 
-        Self {
+    pub fn builder(client_id: String, client_secret: String) -> ClientBuilder {
+        ClientBuilder {
             client_id,
             client_secret,
-            url: "https://api.pluggy.ai".to_string(),
-            client,
+            url: DEFAULT_URL.to_string(),
+            transport: None,
+            cassette: None,
+            retry_policy: RetryPolicy::none(),
+            rate_limit: None,
+            max_in_flight: None,
+            metrics_hook: None,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Time requests spent waiting for the rate limit and the in-flight cap.
+    pub fn limit_metrics(&self) -> LimitMetricsSnapshot {
+        self.limit_metrics.snapshot()
+    }
+
+    fn report_item(&self, item: &Item) {
+        if let Some(reporter) = &self.item_reporter {
+            reporter.report(item);
         }
     }
 
+    pub(crate) async fn send(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+        self.dispatch(request)
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)
+    }
+
+    /// Sends `request` through the middlewares, keeping the transport's
+    /// `Send` error for futures that have to stay `Send`.
+    pub(crate) fn dispatch(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Next::new(&self.middlewares, self.transport.as_ref()).run(request)
+    }
+
     pub fn new_from_env() -> Result<Self, Box<dyn std::error::Error>> {
         let client_id = dotenv::var("PLUGGY_CLIENT_ID")?;
         let client_secret = dotenv::var("PLUGGY_CLIENT_SECRET")?;
@@ -52,7 +228,7 @@ impl Client {
         Ok((client, connect_token))
     }
 
-    async fn create_api_key(&self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn create_api_key(&self) -> Result<String, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/auth", self.url))?;
 
         let payload = AuthRequest {
@@ -61,17 +237,16 @@ impl Client {
             non_expiring: None,
         };
 
-        let json_payload = serde_json::to_string(&payload)?;
+        let json_payload = serde_json::to_vec(&payload)?;
 
         let request = Request::builder()
             .method(Method::POST)
             .uri(url.as_str())
             .header("Content-Type", "application/json")
-            .body(Body::from(json_payload))?;
+            .body(json_payload)?;
 
-        let response = self.client.request(request).await?;
-        let body = hyper::body::to_bytes(response.into_body()).await?;
-        let body = String::from_utf8(body.to_vec())?;
+        let response = self.send(request).await?;
+        let body = String::from_utf8(response.into_body())?;
         let json: serde_json::Value = serde_json::from_str(&body)?;
         let api_key = json["apiKey"].as_str();
 
@@ -87,12 +262,10 @@ impl Client {
     ) -> Result<String, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/connect_token", self.url))?;
 
-        let request =
-            authenticated_request_builder(Method::POST, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let request = authenticated_request_builder(Method::POST, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let body = String::from_utf8(body.to_vec())?;
+        let body = String::from_utf8(response.into_body())?;
         let json: serde_json::Value = serde_json::from_str(&body)?;
         let connect_token = json["accessToken"].as_str();
 
@@ -107,33 +280,59 @@ impl Client {
         api_key: &str,
         with_sandbox: bool,
     ) -> Result<Vec<Connector>, Box<dyn std::error::Error>> {
-        let mut url = Url::parse(&format!("{}/connectors", self.url))?;
-        url.query_pairs_mut()
-            .append_pair("sandbox", &with_sandbox.to_string());
+        let filter = ConnectorFilter {
+            sandbox: with_sandbox,
+            ..Default::default()
+        };
 
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        self.search_connectors(api_key, &filter).await
+    }
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: PageResponse<Connector> = serde_json::from_slice(&body)?;
+    pub async fn search_connectors(
+        &self,
+        api_key: &str,
+        filter: &ConnectorFilter,
+    ) -> Result<Vec<Connector>, Box<dyn std::error::Error>> {
+        let pages = self.stream_connectors(api_key, filter, PageOptions::default())?;
+        pages.collect_all().await
+    }
 
-        Ok(json.results)
+    pub fn stream_connectors<'a>(
+        &'a self,
+        api_key: &'a str,
+        filter: &'a ConnectorFilter,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, Connector>, Box<dyn std::error::Error>> {
+        self.connector_pages(api_key, filter, options)
+            .map_err(|error| error as Box<dyn std::error::Error>)
+    }
+
+    /// `stream_connectors` failing with an error that can be sent across
+    /// threads.
+    pub(crate) fn connector_pages<'a>(
+        &'a self,
+        api_key: &'a str,
+        filter: &'a ConnectorFilter,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, Connector>, TransportError> {
+        let mut url = Url::parse(&format!("{}/connectors", self.url))?;
+        filter.append_query_pairs(&mut url)?;
+
+        Ok(Paginated::new(self, api_key, url, options)
+            .with_filter(|connector| filter.matches(connector)))
     }
 
     pub async fn get_connector(
         &self,
         api_key: &str,
-        connector_id: &str,
+        connector_id: ConnectorId,
     ) -> Result<Connector, Box<dyn std::error::Error>> {
         let url: Url = Url::parse(&format!("{}/connectors/{}", self.url, connector_id))?;
 
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Connector = serde_json::from_slice(&body)?;
+        let json: Connector = serde_json::from_slice(response.body())?;
 
         Ok(json)
     }
@@ -141,16 +340,15 @@ impl Client {
     pub async fn get_item(
         &self,
         api_key: &str,
-        item_id: &str,
+        item_id: ItemId,
     ) -> Result<Item, Box<dyn std::error::Error>> {
         let url: Url = Url::parse(&format!("{}/items/{}", self.url, item_id))?;
 
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Item = serde_json::from_slice(&body)?;
+        let json: Item = serde_json::from_slice(response.body())?;
+        self.report_item(&json);
 
         Ok(json)
     }
@@ -158,7 +356,7 @@ impl Client {
     pub async fn validate_parameters(
         &self,
         api_key: &str,
-        connector_id: i32,
+        connector_id: ConnectorId,
         parameters: &HashMap<&str, &str>,
     ) -> Result<ValidationResult, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!(
@@ -166,11 +364,10 @@ impl Client {
             self.url, connector_id
         ))?;
         let request = authenticated_request_builder(Method::POST, &url, api_key)
-            .body(Body::from(serde_json::to_string(parameters)?))?;
-        let response = self.client.request(request).await?;
+            .body(serde_json::to_vec(parameters)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: ValidationResult = serde_json::from_slice(&body)?;
+        let json: ValidationResult = serde_json::from_slice(response.body())?;
 
         Ok(json)
     }
@@ -178,22 +375,43 @@ impl Client {
     pub async fn create_item(
         &self,
         api_key: &str,
-        connector_id: i32,
+        connector_id: ConnectorId,
+        parameters: &HashMap<String, String>,
+    ) -> Result<Item, Box<dyn std::error::Error>> {
+        self.create_item_with_options(
+            api_key,
+            connector_id,
+            parameters,
+            &CreateItemOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn create_item_with_options(
+        &self,
+        api_key: &str,
+        connector_id: ConnectorId,
         parameters: &HashMap<String, String>,
+        options: &CreateItemOptions<'_>,
     ) -> Result<Item, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/items", self.url))?;
 
         let create_item_request = CreateItemRequest {
             connector_id,
             parameters,
+            client_user_id: options.client_user_id,
+            webhook_url: options.webhook_url,
         };
 
-        let request = authenticated_request_builder(Method::POST, &url, api_key)
-            .body(Body::from(serde_json::to_string(&create_item_request)?))?;
-        let response = self.client.request(request).await?;
+        let request = with_idempotency_key(
+            authenticated_request_builder(Method::POST, &url, api_key),
+            options.idempotency_key,
+        )
+        .body(serde_json::to_vec(&create_item_request)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Item = serde_json::from_slice(&body)?;
+        let json: Item = serde_json::from_slice(response.body())?;
+        self.report_item(&json);
 
         Ok(json)
     }
@@ -201,7 +419,7 @@ impl Client {
     pub async fn update_item(
         &self,
         api_key: &str,
-        item_id: &str,
+        item_id: ItemId,
         parameters: &HashMap<String, String>,
     ) -> Result<Item, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/items/{}", self.url, item_id))?;
@@ -209,11 +427,11 @@ impl Client {
         let update_item_request = UpdateItemRequest { parameters };
 
         let request = authenticated_request_builder(Method::PATCH, &url, api_key)
-            .body(Body::from(serde_json::to_string(&update_item_request)?))?;
-        let response = self.client.request(request).await?;
+            .body(serde_json::to_vec(&update_item_request)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Item = serde_json::from_slice(&body)?;
+        let json: Item = serde_json::from_slice(response.body())?;
+        self.report_item(&json);
 
         Ok(json)
     }
@@ -221,20 +439,19 @@ impl Client {
     pub async fn update_item_mfa_credentials(
         &self,
         api_key: &str,
-        item_id: &str,
+        item_id: ItemId,
         parameters: &HashMap<String, String>,
     ) -> Result<Item, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/items/{}/mfa", self.url, item_id))?;
 
         let update_item_mfa_credentials_request = UpdateItemRequest { parameters };
 
-        let request = authenticated_request_builder(Method::PATCH, &url, api_key).body(
-            Body::from(serde_json::to_string(&update_item_mfa_credentials_request)?),
-        )?;
-        let response = self.client.request(request).await?;
+        let request = authenticated_request_builder(Method::PATCH, &url, api_key)
+            .body(serde_json::to_vec(&update_item_mfa_credentials_request)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Item = serde_json::from_slice(&body)?;
+        let json: Item = serde_json::from_slice(response.body())?;
+        self.report_item(&json);
 
         Ok(json)
     }
@@ -242,13 +459,13 @@ impl Client {
     pub async fn delete_item(
         &self,
         api_key: &str,
-        item_id: &str,
+        item_id: ItemId,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/items/{}", self.url, item_id))?;
 
         let request =
-            authenticated_request_builder(Method::DELETE, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+            authenticated_request_builder(Method::DELETE, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
         if response.status() != StatusCode::OK {
             return Err("Failed to delete item".into());
@@ -261,31 +478,111 @@ impl Client {
         &self,
         api_key: &str,
     ) -> Result<Vec<Category>, Box<dyn std::error::Error>> {
-        let url = Url::parse(&format!("{}/categories", self.url))?;
-
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let pages = self.stream_categories(api_key, PageOptions::default())?;
+        pages.collect_all().await
+    }
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: PageResponse<Category> = serde_json::from_slice(&body)?;
+    pub fn stream_categories<'a>(
+        &'a self,
+        api_key: &'a str,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, Category>, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/categories", self.url))?;
 
-        Ok(json.results)
+        Ok(Paginated::new(self, api_key, url, options))
     }
 
     pub async fn get_category(
         &self,
         api_key: &str,
-        category_id: &str,
+        category_id: &CategoryId,
     ) -> Result<Category, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/categories/{}", self.url, category_id))?;
 
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: Category = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn get_category_rules(
+        &self,
+        api_key: &str,
+    ) -> Result<Vec<CategoryRule>, Box<dyn std::error::Error>> {
+        let pages = self.stream_category_rules(api_key, PageOptions::default())?;
+        pages.collect_all().await
+    }
+
+    pub fn stream_category_rules<'a>(
+        &'a self,
+        api_key: &'a str,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, CategoryRule>, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/categories/rules", self.url))?;
+
+        Ok(Paginated::new(self, api_key, url, options))
+    }
+
+    pub async fn create_category_rule(
+        &self,
+        api_key: &str,
+        description: &str,
+        category_id: &CategoryId,
+    ) -> Result<CategoryRule, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/categories/rules", self.url))?;
+
+        let create_category_rule_request = CreateCategoryRuleRequest {
+            description,
+            category_id,
+        };
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key)
+            .body(serde_json::to_vec(&create_category_rule_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: CategoryRule = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn delete_category_rule(
+        &self,
+        api_key: &str,
+        category_rule_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!(
+            "{}/categories/rules/{}",
+            self.url, category_rule_id
+        ))?;
+
         let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+            authenticated_request_builder(Method::DELETE, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        if response.status() != StatusCode::OK {
+            return Err("Failed to delete category rule".into());
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_transaction_category(
+        &self,
+        api_key: &str,
+        transaction_id: &str,
+        category_id: &CategoryId,
+    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/transactions/{}", self.url, transaction_id))?;
+
+        let update_transaction_category_request = UpdateTransactionCategoryRequest { category_id };
+
+        let request = authenticated_request_builder(Method::PATCH, &url, api_key)
+            .body(serde_json::to_vec(&update_transaction_category_request)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Category = serde_json::from_slice(&body)?;
+        let json: Transaction = serde_json::from_slice(response.body())?;
 
         Ok(json)
     }
@@ -294,31 +591,31 @@ impl Client {
         &self,
         api_key: &str,
     ) -> Result<Vec<Webhook>, Box<dyn std::error::Error>> {
-        let url = Url::parse(&format!("{}/webhooks", self.url))?;
-
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let pages = self.stream_webhooks(api_key, PageOptions::default())?;
+        pages.collect_all().await
+    }
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: PageResponse<Webhook> = serde_json::from_slice(&body)?;
+    pub fn stream_webhooks<'a>(
+        &'a self,
+        api_key: &'a str,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, Webhook>, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/webhooks", self.url))?;
 
-        Ok(json.results)
+        Ok(Paginated::new(self, api_key, url, options))
     }
 
     pub async fn get_webhook(
         &self,
         api_key: &str,
-        webhook_id: &str,
+        webhook_id: WebhookId,
     ) -> Result<Webhook, Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/webhooks/{}", self.url, webhook_id))?;
 
-        let request =
-            authenticated_request_builder(Method::GET, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let json: Webhook = serde_json::from_slice(&body)?;
+        let json: Webhook = serde_json::from_slice(response.body())?;
 
         Ok(json)
     }
@@ -338,11 +635,10 @@ impl Client {
         };
 
         let request = authenticated_request_builder(Method::POST, &request_url, api_key)
-            .body(Body::from(serde_json::to_string(&create_webhook_request)?))?;
-        let response = self.client.request(request).await?;
+            .body(serde_json::to_vec(&create_webhook_request)?)?;
+        let response = self.send(request).await?;
 
-        let body: hyper::body::Bytes = hyper::body::to_bytes(response.into_body()).await?;
-        let webhook: Webhook = serde_json::from_slice(&body)?;
+        let webhook: Webhook = serde_json::from_slice(response.body())?;
 
         Ok(webhook)
     }
@@ -350,13 +646,13 @@ impl Client {
     pub async fn delete_webhook(
         &self,
         api_key: &str,
-        webhook_id: &str,
+        webhook_id: WebhookId,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let url = Url::parse(&format!("{}/webhooks/{}", self.url, webhook_id))?;
 
         let request =
-            authenticated_request_builder(Method::DELETE, &url, api_key).body(Body::empty())?;
-        let response = self.client.request(request).await?;
+            authenticated_request_builder(Method::DELETE, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
 
         if response.status() != StatusCode::OK {
             return Err("Failed to delete webhook".into());
@@ -364,44 +660,425 @@ impl Client {
 
         Ok(())
     }
+
+    pub async fn create_smart_transfer_preauthorization(
+        &self,
+        api_key: &str,
+        connector_id: ConnectorId,
+        parameters: &HashMap<String, String>,
+        recipient_ids: &[String],
+    ) -> Result<SmartTransferPreauthorization, Box<dyn std::error::Error>> {
+        self.create_smart_transfer_preauthorization_with_options(
+            api_key,
+            connector_id,
+            parameters,
+            recipient_ids,
+            &CreateSmartTransferPreauthorizationOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn create_smart_transfer_preauthorization_with_options(
+        &self,
+        api_key: &str,
+        connector_id: ConnectorId,
+        parameters: &HashMap<String, String>,
+        recipient_ids: &[String],
+        options: &CreateSmartTransferPreauthorizationOptions<'_>,
+    ) -> Result<SmartTransferPreauthorization, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/smart-transfers/preauthorizations", self.url))?;
+
+        let create_preauthorization_request = CreateSmartTransferPreauthorizationRequest {
+            connector_id,
+            parameters,
+            recipient_ids,
+            callback_urls: options.callback_urls,
+            client_preauthorization_id: options.client_preauthorization_id,
+        };
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key)
+            .body(serde_json::to_vec(&create_preauthorization_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: SmartTransferPreauthorization = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn get_smart_transfer_preauthorizations(
+        &self,
+        api_key: &str,
+    ) -> Result<Vec<SmartTransferPreauthorization>, Box<dyn std::error::Error>> {
+        let pages =
+            self.stream_smart_transfer_preauthorizations(api_key, PageOptions::default())?;
+        pages.collect_all().await
+    }
+
+    pub fn stream_smart_transfer_preauthorizations<'a>(
+        &'a self,
+        api_key: &'a str,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, SmartTransferPreauthorization>, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/smart-transfers/preauthorizations", self.url))?;
+
+        Ok(Paginated::new(self, api_key, url, options))
+    }
+
+    pub async fn get_smart_transfer_preauthorization(
+        &self,
+        api_key: &str,
+        preauthorization_id: &str,
+    ) -> Result<SmartTransferPreauthorization, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!(
+            "{}/smart-transfers/preauthorizations/{}",
+            self.url, preauthorization_id
+        ))?;
+
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: SmartTransferPreauthorization = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn create_smart_transfer_payment(
+        &self,
+        api_key: &str,
+        preauthorization_id: &str,
+        recipient_id: &str,
+        amount: &Money,
+        description: Option<&str>,
+    ) -> Result<SmartTransferPayment, Box<dyn std::error::Error>> {
+        self.create_smart_transfer_payment_with_idempotency_key(
+            api_key,
+            preauthorization_id,
+            recipient_id,
+            amount,
+            &CreateSmartTransferPaymentOptions {
+                description,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Like `create_smart_transfer_payment`, sending `idempotency_key` as the
+    /// `Idempotency-Key` header so the payment can be retried safely.
+    pub async fn create_smart_transfer_payment_with_idempotency_key(
+        &self,
+        api_key: &str,
+        preauthorization_id: &str,
+        recipient_id: &str,
+        amount: &Money,
+        options: &CreateSmartTransferPaymentOptions<'_>,
+        idempotency_key: Option<&str>,
+    ) -> Result<SmartTransferPayment, Box<dyn std::error::Error>> {
+        amount.ensure_currency(&Currency::BRL)?;
+        let url = Url::parse(&format!("{}/smart-transfers/payments", self.url))?;
+
+        let create_payment_request = CreateSmartTransferPaymentRequest {
+            preauthorization_id,
+            recipient_id,
+            amount,
+            description: options.description,
+            client_payment_id: options.client_payment_id,
+        };
+
+        let request = with_idempotency_key(
+            authenticated_request_builder(Method::POST, &url, api_key),
+            idempotency_key,
+        )
+        .body(serde_json::to_vec(&create_payment_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: SmartTransferPayment = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn get_smart_transfer_payment(
+        &self,
+        api_key: &str,
+        payment_id: &str,
+    ) -> Result<SmartTransferPayment, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!(
+            "{}/smart-transfers/payments/{}",
+            self.url, payment_id
+        ))?;
+
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: SmartTransferPayment = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn create_boleto_connection(
+        &self,
+        api_key: &str,
+        connector_id: ConnectorId,
+        credentials: &HashMap<String, String>,
+    ) -> Result<BoletoConnection, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/boleto-connections", self.url))?;
+
+        let create_boleto_connection_request = CreateBoletoConnectionRequest {
+            connector_id,
+            credentials,
+        };
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key)
+            .body(serde_json::to_vec(&create_boleto_connection_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: BoletoConnection = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn issue_boleto(
+        &self,
+        api_key: &str,
+        boleto_connection_id: &str,
+        boleto: &NewBoleto,
+    ) -> Result<Boleto, Box<dyn std::error::Error>> {
+        boleto.amount.ensure_currency(&Currency::BRL)?;
+        let url = Url::parse(&format!("{}/boletos", self.url))?;
+
+        let issue_boleto_request = IssueBoletoRequest {
+            boleto_connection_id,
+            boleto,
+        };
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key)
+            .body(serde_json::to_vec(&issue_boleto_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: Boleto = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn get_boleto(
+        &self,
+        api_key: &str,
+        boleto_id: &str,
+    ) -> Result<Boleto, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/boletos/{}", self.url, boleto_id))?;
+
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: Boleto = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn cancel_boleto(
+        &self,
+        api_key: &str,
+        boleto_id: &str,
+    ) -> Result<Boleto, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/boletos/{}/cancel", self.url, boleto_id))?;
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: Boleto = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn create_boleto_payment_request(
+        &self,
+        api_key: &str,
+        digitable_line: &str,
+        description: Option<&str>,
+    ) -> Result<PaymentRequest, Box<dyn std::error::Error>> {
+        self.create_boleto_payment_request_with_idempotency_key(
+            api_key,
+            digitable_line,
+            description,
+            None,
+        )
+        .await
+    }
+
+    /// Like `create_boleto_payment_request`, sending `idempotency_key` as the
+    /// `Idempotency-Key` header so the request can be retried safely.
+    pub async fn create_boleto_payment_request_with_idempotency_key(
+        &self,
+        api_key: &str,
+        digitable_line: &str,
+        description: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> Result<PaymentRequest, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/payments/requests", self.url))?;
+
+        let digitable_line = DigitableLine::parse(digitable_line)?;
+        let create_payment_request = CreateBoletoPaymentRequest {
+            boleto: BoletoPaymentRequestBoleto {
+                digitable_line: digitable_line.as_str(),
+            },
+            description,
+        };
+
+        let request = with_idempotency_key(
+            authenticated_request_builder(Method::POST, &url, api_key),
+            idempotency_key,
+        )
+        .body(serde_json::to_vec(&create_payment_request)?)?;
+        let response = self.send(request).await?;
+
+        let json: PaymentRequest = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn get_consents(
+        &self,
+        api_key: &str,
+        item_id: ItemId,
+    ) -> Result<Vec<Consent>, Box<dyn std::error::Error>> {
+        let pages = self.stream_consents(api_key, item_id, PageOptions::default())?;
+        pages.collect_all().await
+    }
+
+    pub fn stream_consents<'a>(
+        &'a self,
+        api_key: &'a str,
+        item_id: ItemId,
+        options: PageOptions,
+    ) -> Result<Paginated<'a, Consent>, Box<dyn std::error::Error>> {
+        let mut url = Url::parse(&format!("{}/consents", self.url))?;
+        url.query_pairs_mut()
+            .append_pair("itemId", &item_id.to_string());
+
+        Ok(Paginated::new(self, api_key, url, options))
+    }
+
+    pub async fn get_consent(
+        &self,
+        api_key: &str,
+        consent_id: &str,
+    ) -> Result<Consent, Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/consents/{}", self.url, consent_id))?;
+
+        let request = authenticated_request_builder(Method::GET, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        let json: Consent = serde_json::from_slice(response.body())?;
+
+        Ok(json)
+    }
+
+    pub async fn revoke_consent(
+        &self,
+        api_key: &str,
+        consent_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let url = Url::parse(&format!("{}/consents/{}/revoke", self.url, consent_id))?;
+
+        let request = authenticated_request_builder(Method::POST, &url, api_key).body(Vec::new())?;
+        let response = self.send(request).await?;
+
+        if response.status() != StatusCode::OK {
+            return Err("Failed to revoke consent".into());
+        }
+
+        Ok(())
+    }
 }
 
-#[cfg(test)]
-mod tests {
+#[cfg(all(test, feature = "hyper"))]
+pub(crate) mod tests {
     use super::*;
+    use crate::testing::{ItemStep, MockServer, MOCK_RECIPIENT_ID};
 
-    const TEST_ITAU_ITEM_ID: &str = "e22c7308-7031-47f0-88a3-462f44d96f70";
-    const TEST_SANDBOX_ITEM_ID: &str = "e97238a7-7f5c-4667-8497-5ed8ac4fb509";
-    const TEST_WEBHOOK_ID: &str = "6903e8ab-5858-460c-9c6b-2e367ac0d3e9";
+    const TEST_ITAU_ITEM_ID: ItemId =
+        ItemId(Uuid::from_u128(0xe22c7308_7031_47f0_88a3_462f44d96f70));
+    const TEST_SANDBOX_ITEM_ID: ItemId =
+        ItemId(Uuid::from_u128(0xe97238a7_7f5c_4667_8497_5ed8ac4fb509));
+    const TEST_WEBHOOK_URL: &str = "https://some.site/pluggy-notifications";
+
+    fn sandbox_parameters() -> HashMap<String, String> {
+        HashMap::from([
+            ("user".to_string(), "user-ok".to_string()),
+            ("password".to_string(), "password-ok".to_string()),
+        ])
+    }
+
+    fn has_credentials() -> bool {
+        dotenv::var("PLUGGY_CLIENT_ID").is_ok() && dotenv::var("PLUGGY_CLIENT_SECRET").is_ok()
+    }
+
+    /// Client for the tests that talk to Pluggy, which are ignored until
+    /// their recording is committed. With credentials in the environment
+    /// they run live, recording the traffic to `cassettes/<test>.json` when
+    /// `PLUGGY_CASSETTE=record`. Without credentials, or with
+    /// `PLUGGY_CASSETTE=replay`, they replay that recording.
+    pub(crate) async fn test_client(test: &str) -> (Client, String) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("cassettes")
+            .join(format!("{}.json", test));
+        let mode = dotenv::var("PLUGGY_CASSETTE").ok();
+
+        let builder = if mode.as_deref() == Some("replay") || !has_credentials() {
+            assert!(
+                path.exists(),
+                "{} needs Pluggy credentials or {}",
+                test,
+                path.display()
+            );
+            Client::builder(String::new(), String::new()).cassette(Cassette::replay(path).unwrap())
+        } else if mode.as_deref() == Some("record") {
+            Client::builder(
+                dotenv::var("PLUGGY_CLIENT_ID").unwrap(),
+                dotenv::var("PLUGGY_CLIENT_SECRET").unwrap(),
+            )
+            .cassette(Cassette::record(path))
+        } else {
+            return Client::new_from_env_with_api_key().await.unwrap();
+        };
+
+        let client = builder.build();
+        let api_key = client.create_api_key().await.unwrap();
+        (client, api_key)
+    }
 
     #[test]
     fn can_instantiate_from_env() {
         let result = Client::new_from_env();
-        assert!(result.is_ok());
+        assert_eq!(result.is_ok(), has_credentials());
     }
 
     #[tokio::test]
+    #[ignore = "needs Pluggy credentials"]
     async fn can_instantiate_from_env_with_api_key() {
         let result = Client::new_from_env_with_api_key().await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
+    #[ignore = "needs Pluggy credentials or a recorded cassette"]
     async fn can_create_connect_token() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let (client, api_key) = test_client("can_create_connect_token").await;
         let connect_token = client.create_connect_token(&api_key).await.unwrap();
         assert_eq!(connect_token.len(), 892);
     }
 
     #[tokio::test]
     async fn can_get_connectors() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
         let connectors = client.get_connectors(&api_key, false).await.unwrap();
-        let connector = connectors.iter().find(|c| c.id == 201);
+        let connector = connectors.iter().find(|c| c.id == ConnectorId(201));
 
         match connector {
             Some(connector) => {
-                assert_eq!(connector.id, 201);
+                assert_eq!(connector.id, ConnectorId(201));
                 assert_eq!(connector.name, "Itaú");
             }
             None => panic!("No connector found"),
@@ -410,15 +1087,16 @@ mod tests {
 
     #[tokio::test]
     async fn can_get_connectors_with_sandbox() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
         let connectors = client.get_connectors(&api_key, true).await.unwrap();
-        let connector = connectors.iter().find(|c| c.id == 2);
+        let connector = connectors.iter().find(|c| c.id == ConnectorId(2));
 
         assert!(connector.is_some());
 
         match connector {
             Some(connector) => {
-                assert_eq!(connector.id, 2);
+                assert_eq!(connector.id, ConnectorId(2));
                 assert_eq!(connector.name, "Pluggy Bank");
             }
             None => panic!("No connector found"),
@@ -427,50 +1105,121 @@ mod tests {
 
     #[tokio::test]
     async fn can_get_connectors_without_sandbox() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
         let connectors = client.get_connectors(&api_key, false).await.unwrap();
-        let connector = connectors.iter().find(|c| c.id == 2);
+        let connector = connectors.iter().find(|c| c.id == ConnectorId(2));
 
         assert!(connector.is_none());
     }
 
+    #[tokio::test]
+    async fn can_search_connectors() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let itau = serde_json::to_value(
+            client
+                .get_connector(&api_key, ConnectorId(201))
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let variant = |id: i32, field: &str, value: serde_json::Value| {
+            let mut connector = itau.clone();
+            connector["id"] = id.into();
+            connector[field] = value;
+            connector
+        };
+        server.add_connector(variant(202, "country", "AR".into()), false);
+        server.add_connector(variant(203, "type", "BUSINESS_BANK".into()), false);
+        server.add_connector(
+            variant(204, "products", serde_json::json!(["ACCOUNTS"])),
+            false,
+        );
+        server.add_connector(variant(205, "name", "Nubank".into()), false);
+        server.set_connector_health(ConnectorId(205), ConnectorStatus::Offline);
+
+        let mut filter = ConnectorFilter {
+            countries: vec![Country::BR],
+            types: vec![ConnectorType::PersonalBank],
+            products: vec![ProductType::Transactions],
+            ..Default::default()
+        };
+        let ids = |connectors: Vec<Connector>| -> Vec<ConnectorId> {
+            connectors.into_iter().map(|c| c.id).collect()
+        };
+        let connectors = client.search_connectors(&api_key, &filter).await.unwrap();
+        assert_eq!(ids(connectors), vec![ConnectorId(201), ConnectorId(205)]);
+
+        filter.sandbox = true;
+        filter.health_statuses = vec![ConnectorStatus::Online];
+        let connectors = client.search_connectors(&api_key, &filter).await.unwrap();
+        assert_eq!(ids(connectors), vec![ConnectorId(201), ConnectorId(2)]);
+
+        filter.name = Some("nu".to_string());
+        filter.health_statuses.clear();
+        let connectors = client.search_connectors(&api_key, &filter).await.unwrap();
+        assert_eq!(ids(connectors), vec![ConnectorId(205)]);
+    }
+
     #[tokio::test]
     async fn can_get_connector() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let connector = client.get_connector(&api_key, "201").await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let connector = client
+            .get_connector(&api_key, ConnectorId(201))
+            .await
+            .unwrap();
 
-        assert_eq!(connector.id, 201);
+        assert_eq!(connector.id, ConnectorId(201));
         assert_eq!(connector.name, "Itaú");
     }
 
     #[tokio::test]
     async fn can_get_connector_with_sandbox() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let connector = client.get_connector(&api_key, "2").await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let connector = client
+            .get_connector(&api_key, ConnectorId(2))
+            .await
+            .unwrap();
 
-        assert_eq!(connector.id, 2);
+        assert_eq!(connector.id, ConnectorId(2));
         assert_eq!(connector.name, "Pluggy Bank");
     }
 
     #[tokio::test]
     async fn can_get_item() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let item = client.get_item(&api_key, TEST_ITAU_ITEM_ID).await.unwrap();
+        let server = MockServer::start().await;
+        server.script_connector_items(
+            ConnectorId(201),
+            vec![ItemStep::new(
+                ItemStatus::LoginError,
+                ExecutionStatus::Success,
+            )],
+        );
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let created = client
+            .create_item(&api_key, ConnectorId(201), &sandbox_parameters())
+            .await
+            .unwrap();
+        let item = client.get_item(&api_key, created.id).await.unwrap();
 
-        assert_eq!(item.id, TEST_ITAU_ITEM_ID);
+        assert_eq!(item.id, created.id);
         assert!(matches!(item.status, ItemStatus::LoginError));
         assert!(matches!(item.execution_status, ExecutionStatus::Success));
         assert_eq!(item.consecutive_failed_login_attempts, 0);
-        assert_eq!(item.connector.id, 201);
+        assert_eq!(item.connector.id, ConnectorId(201));
         assert_eq!(item.connector.name, "Itaú");
     }
 
     #[tokio::test]
     async fn can_validate_parameters() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
         let parameters = HashMap::from([("user", "user-ok"), ("password", "password-ok")]);
         let result = client
-            .validate_parameters(&api_key, 2, &parameters)
+            .validate_parameters(&api_key, ConnectorId(2), &parameters)
             .await
             .unwrap();
 
@@ -499,53 +1248,88 @@ mod tests {
         }
 
         assert_eq!(result.errors.len(), 0);
+
+        let parameters = HashMap::from([("user", "user-ok")]);
+        let result = client
+            .validate_parameters(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].parameter, "password");
     }
 
     #[tokio::test]
     async fn can_create_item_and_delete() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let parameters = HashMap::from([
-            ("user".to_string(), "user-ok".to_string()),
-            ("password".to_string(), "password-ok".to_string()),
-        ]);
-        let item = client.create_item(&api_key, 2, &parameters).await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &sandbox_parameters())
+            .await
+            .unwrap();
 
-        assert_eq!(item.id.len(), 36);
         assert!(matches!(item.status, ItemStatus::Updating));
         assert!(matches!(item.execution_status, ExecutionStatus::Created));
         assert_eq!(item.consecutive_failed_login_attempts, 0);
-        assert_eq!(item.connector.id, 2);
+        assert_eq!(item.connector.id, ConnectorId(2));
         assert_eq!(item.connector.name, "Pluggy Bank");
 
-        let result = client.delete_item(&api_key, &item.id).await;
+        let result = client.delete_item(&api_key, item.id).await;
         assert!(result.is_ok());
 
-        let item = client.get_item(&api_key, &item.id).await;
+        let item = client.get_item(&api_key, item.id).await;
         assert!(item.is_err());
     }
 
+    #[tokio::test]
+    async fn can_create_item_with_options() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let options = CreateItemOptions {
+            client_user_id: Some("user-42"),
+            webhook_url: Some("https://example.com/webhooks"),
+            idempotency_key: Some("create-item-42"),
+        };
+        let item = client
+            .create_item_with_options(&api_key, ConnectorId(2), &sandbox_parameters(), &options)
+            .await
+            .unwrap();
+
+        assert_eq!(item.client_user_id.as_deref(), Some("user-42"));
+        assert_eq!(
+            item.webhook_url.as_deref(),
+            Some("https://example.com/webhooks")
+        );
+    }
+
     #[tokio::test]
     async fn can_update_item() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let parameters = HashMap::from([
-            ("user".to_string(), "user-ok".to_string()),
-            ("password".to_string(), "password-ok".to_string()),
-        ]);
+        let server = MockServer::start().await;
+        server.script_connector_items(ConnectorId(2), vec![ItemStep::success()]);
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &sandbox_parameters())
+            .await
+            .unwrap();
+        let item = client.get_item(&api_key, item.id).await.unwrap();
+        assert!(matches!(item.status, ItemStatus::Updated));
+
         let item = client
-            .update_item(&api_key, TEST_SANDBOX_ITEM_ID, &parameters)
+            .update_item(&api_key, item.id, &sandbox_parameters())
             .await
             .unwrap();
 
-        assert_eq!(item.id, TEST_SANDBOX_ITEM_ID);
         assert!(matches!(item.status, ItemStatus::Updating));
         assert!(matches!(item.execution_status, ExecutionStatus::Created));
-        assert_eq!(item.connector.id, 2);
+        assert_eq!(item.connector.id, ConnectorId(2));
         assert_eq!(item.connector.name, "Pluggy Bank");
     }
 
     #[tokio::test]
     async fn can_get_categories() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
         let categories = client.get_categories(&api_key).await.unwrap();
 
         let income_category = categories.iter().find(|c| c.description == "Income");
@@ -555,32 +1339,51 @@ mod tests {
 
     #[tokio::test]
     async fn can_get_category() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let category = client.get_category(&api_key, "01000000").await;
-        assert!(category.is_ok());
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let category = client
+            .get_category(&api_key, &"01010000".into())
+            .await
+            .unwrap();
+
+        assert_eq!(category.description, "Salary");
+        assert_eq!(category.parent_id, Some("01000000".into()));
     }
 
     #[tokio::test]
     async fn can_get_webhooks() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        assert!(client.get_webhooks(&api_key).await.unwrap().is_empty());
+
+        client
+            .create_webhook(&api_key, TEST_WEBHOOK_URL, WebhookEvent::ItemUpdated)
+            .await
+            .unwrap();
         let webhooks = client.get_webhooks(&api_key).await.unwrap();
 
-        assert!(webhooks.len() > 0);
+        assert_eq!(webhooks.len(), 1);
     }
 
     #[tokio::test]
     async fn can_get_webhook() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
-        let webhook = client.get_webhook(&api_key, TEST_WEBHOOK_ID).await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let created = client
+            .create_webhook(&api_key, TEST_WEBHOOK_URL, WebhookEvent::ItemUpdated)
+            .await
+            .unwrap();
+        let webhook = client.get_webhook(&api_key, created.id).await.unwrap();
 
-        assert_eq!(webhook.id, TEST_WEBHOOK_ID);
-        assert_eq!(webhook.url, "https://some.site/pluggy-notifications");
+        assert_eq!(webhook.id, created.id);
+        assert_eq!(webhook.url, TEST_WEBHOOK_URL);
         assert!(matches!(webhook.event, WebhookEvent::ItemUpdated));
     }
 
     #[tokio::test]
     async fn can_create_webhook_and_delete() {
-        let (client, api_key) = Client::new_from_env_with_api_key().await.unwrap();
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
 
         let url = "https://somesite.com/pluggy-notifications";
         let webhook = client
@@ -588,14 +1391,340 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(webhook.id.len(), 36);
         assert_eq!(webhook.url, url);
         assert!(matches!(webhook.event, WebhookEvent::ItemUpdated));
 
-        let result = client.delete_webhook(&api_key, &webhook.id).await;
+        let result = client.delete_webhook(&api_key, webhook.id).await;
         assert!(result.is_ok());
 
-        let webhook = client.get_webhook(&api_key, &webhook.id).await;
+        let webhook = client.get_webhook(&api_key, webhook.id).await;
         assert!(webhook.is_err());
     }
+
+    #[tokio::test]
+    #[ignore = "needs Pluggy credentials or a recorded cassette"]
+    async fn can_get_smart_transfer_preauthorizations() {
+        let (client, api_key) = test_client("can_get_smart_transfer_preauthorizations").await;
+        let preauthorizations = client.get_smart_transfer_preauthorizations(&api_key).await;
+
+        assert!(preauthorizations.is_ok());
+    }
+
+    #[tokio::test]
+    async fn can_create_smart_transfer_preauthorization() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let parameters = HashMap::from([
+            ("user".to_string(), "user-ok".to_string()),
+            ("password".to_string(), "password-ok".to_string()),
+        ]);
+        let callback_urls = SmartTransferCallbackUrls {
+            success: Some("https://example.com/ok".to_string()),
+            error: None,
+        };
+        let preauthorization = client
+            .create_smart_transfer_preauthorization_with_options(
+                &api_key,
+                ConnectorId(2),
+                &parameters,
+                &[MOCK_RECIPIENT_ID.to_string()],
+                &CreateSmartTransferPreauthorizationOptions {
+                    callback_urls: Some(&callback_urls),
+                    client_preauthorization_id: Some("order-7"),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(preauthorization.is_consent_pending());
+        assert!(preauthorization.consent_url.is_some());
+        assert_eq!(preauthorization.connector.unwrap().id, ConnectorId(2));
+        assert_eq!(preauthorization.recipients[0].id, MOCK_RECIPIENT_ID);
+        assert_eq!(preauthorization.callback_urls, Some(callback_urls));
+        assert_eq!(
+            preauthorization.client_preauthorization_id.as_deref(),
+            Some("order-7")
+        );
+
+        let preauthorizations = client
+            .get_smart_transfer_preauthorizations(&api_key)
+            .await
+            .unwrap();
+        assert_eq!(preauthorizations.len(), 1);
+
+        let result = client
+            .create_smart_transfer_preauthorization(
+                &api_key,
+                ConnectorId(2),
+                &parameters,
+                &["unknown".to_string()],
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_create_smart_transfer_payment() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let preauthorization = client
+            .create_smart_transfer_preauthorization(
+                &api_key,
+                ConnectorId(2),
+                &HashMap::new(),
+                &[MOCK_RECIPIENT_ID.to_string()],
+            )
+            .await
+            .unwrap();
+        let amount = Money::brl(Decimal::new(1050, 2));
+
+        let result = client
+            .create_smart_transfer_payment(
+                &api_key,
+                &preauthorization.id,
+                MOCK_RECIPIENT_ID,
+                &amount,
+                None,
+            )
+            .await;
+        assert!(result.is_err());
+
+        server.grant_smart_transfer_consent(&preauthorization.id);
+        let preauthorization = client
+            .get_smart_transfer_preauthorization(&api_key, &preauthorization.id)
+            .await
+            .unwrap();
+        assert!(preauthorization.is_consent_granted());
+
+        let payment = client
+            .create_smart_transfer_payment_with_idempotency_key(
+                &api_key,
+                &preauthorization.id,
+                MOCK_RECIPIENT_ID,
+                &amount,
+                &CreateSmartTransferPaymentOptions {
+                    description: Some("Pedido 7"),
+                    client_payment_id: Some("order-7"),
+                },
+                Some("order-7-payment"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(payment.preauthorization_id, preauthorization.id);
+        assert_eq!(payment.recipient_id, MOCK_RECIPIENT_ID);
+        assert_eq!(payment.amount, amount);
+        assert_eq!(payment.description.as_deref(), Some("Pedido 7"));
+        assert_eq!(payment.client_payment_id.as_deref(), Some("order-7"));
+        assert!(matches!(
+            payment.status,
+            SmartTransferPaymentStatus::Created
+        ));
+        assert_eq!(
+            client
+                .get_smart_transfer_payment(&api_key, &payment.id)
+                .await
+                .unwrap(),
+            payment
+        );
+    }
+
+    #[tokio::test]
+    async fn can_not_create_boleto_payment_request_with_invalid_line() {
+        let client = Client::new("client_id".to_string(), "client_secret".to_string());
+        let result = client
+            .create_boleto_payment_request("api_key", "00190.00009 01234.567806", None)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_issue_boleto_and_cancel() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let credentials = HashMap::from([("clientId".to_string(), "inter-id".to_string())]);
+        let connection = client
+            .create_boleto_connection(&api_key, ConnectorId(201), &credentials)
+            .await
+            .unwrap();
+        assert_eq!(connection.connector_id, ConnectorId(201));
+
+        let new_boleto: NewBoleto = serde_json::from_value(serde_json::json!({
+            "seuNumero": "INV-2023-0042",
+            "amount": 123.45,
+            "dueDate": "2025-02-22",
+            "payer": { "taxNumber": "52998224725", "name": "Maria Souza" },
+            "fine": 2.0,
+            "interest": 1.0,
+        }))
+        .unwrap();
+        let boleto = client
+            .issue_boleto(&api_key, &connection.id, &new_boleto)
+            .await
+            .unwrap();
+
+        assert!(matches!(boleto.status, BoletoStatus::Open));
+        assert_eq!(boleto.boleto_connection_id, connection.id);
+        assert_eq!(boleto.seu_numero.as_deref(), Some("INV-2023-0042"));
+        assert_eq!(boleto.amount, new_boleto.amount);
+        assert_eq!(boleto.due_date, new_boleto.due_date);
+        assert_eq!(boleto.payer, new_boleto.payer);
+        assert_eq!(boleto.fine, Some(Decimal::new(20, 1)));
+        assert_eq!(boleto.interest, new_boleto.interest);
+        assert_eq!(
+            client.get_boleto(&api_key, &boleto.id).await.unwrap(),
+            boleto
+        );
+
+        let boleto = client.cancel_boleto(&api_key, &boleto.id).await.unwrap();
+        assert!(matches!(boleto.status, BoletoStatus::Cancelled));
+        assert!(client.cancel_boleto(&api_key, &boleto.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_create_boleto_payment_request() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let payment_request = client
+            .create_boleto_payment_request_with_idempotency_key(
+                &api_key,
+                "00190.00009 01234.567806 00000.000174 7 10000000012345",
+                Some("Conta de luz"),
+                Some("light-bill-2025-02"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(payment_request.amount, Money::brl(Decimal::new(12345, 2)));
+        assert_eq!(payment_request.description.as_deref(), Some("Conta de luz"));
+        assert_eq!(
+            payment_request.boleto.unwrap().digitable_line,
+            "00190000090123456780600000000174710000000012345"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Pluggy credentials or a recorded cassette"]
+    async fn can_get_consents() {
+        let (client, api_key) = test_client("can_get_consents").await;
+        let consents = client.get_consents(&api_key, TEST_ITAU_ITEM_ID).await;
+
+        assert!(consents.is_ok());
+    }
+
+    #[tokio::test]
+    async fn can_get_consents_and_revoke() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let consent = |id: &str, item_id: ItemId| {
+            serde_json::json!({
+                "id": id,
+                "itemId": item_id,
+                "products": ["ACCOUNTS", "TRANSACTIONS"],
+                "openFinancePermissionsGranted": ["ACCOUNTS_READ"],
+                "createdAt": "2023-05-02T12:00:00.000Z",
+                "expiresAt": null,
+                "revokedAt": null,
+            })
+        };
+        server.add_consent(consent("consent-1", TEST_ITAU_ITEM_ID));
+        server.add_consent(consent("consent-2", TEST_SANDBOX_ITEM_ID));
+
+        let consents = client
+            .get_consents(&api_key, TEST_ITAU_ITEM_ID)
+            .await
+            .unwrap();
+        assert_eq!(consents.len(), 1);
+        assert_eq!(consents[0].id, "consent-1");
+        assert_eq!(consents[0].item_id, TEST_ITAU_ITEM_ID);
+        assert!(!consents[0].is_revoked());
+
+        let result = client.revoke_consent(&api_key, "consent-1").await;
+        assert!(result.is_ok());
+
+        let consent = client.get_consent(&api_key, "consent-1").await.unwrap();
+        assert!(consent.is_revoked());
+        assert!(client.revoke_consent(&api_key, "consent-1").await.is_err());
+        assert!(client.get_consent(&api_key, "unknown").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_create_category_rule_and_delete_offline() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let rule = client
+            .create_category_rule(&api_key, "UBER *TRIP", &"08000000".into())
+            .await
+            .unwrap();
+        assert_eq!(rule.description, "UBER *TRIP");
+        assert_eq!(rule.category_id.as_str(), "08000000");
+
+        let rules = client.get_category_rules(&api_key).await.unwrap();
+        assert_eq!(rules, vec![rule.clone()]);
+
+        let result = client.delete_category_rule(&api_key, &rule.id).await;
+        assert!(result.is_ok());
+        assert!(client
+            .get_category_rules(&api_key)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(client
+            .delete_category_rule(&api_key, &rule.id)
+            .await
+            .is_err());
+        assert!(client
+            .create_category_rule(&api_key, "UBER *TRIP", &"99999999".into())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn can_update_transaction_category() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+        let transactions: serde_json::Value =
+            serde_json::from_str(include_str!("resources/fixtures/transactions.json")).unwrap();
+        let transaction = transactions["results"][0].clone();
+        let transaction_id = transaction["id"].as_str().unwrap().to_string();
+        server.add_transaction(transaction);
+
+        let transaction = client
+            .update_transaction_category(&api_key, &transaction_id, &"01010000".into())
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.id, transaction_id);
+        assert_eq!(transaction.category_id.unwrap().as_str(), "01010000");
+        assert_eq!(transaction.category.as_deref(), Some("Salary"));
+        assert!(client
+            .update_transaction_category(&api_key, "unknown", &"01010000".into())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Pluggy credentials or a recorded cassette"]
+    async fn can_create_category_rule_and_delete() {
+        let (client, api_key) = test_client("can_create_category_rule_and_delete").await;
+
+        let rule = client
+            .create_category_rule(&api_key, "UBER *TRIP", &"01000000".into())
+            .await
+            .unwrap();
+
+        assert_eq!(rule.description, "UBER *TRIP");
+        assert_eq!(rule.category_id.as_str(), "01000000");
+
+        let rules = client.get_category_rules(&api_key).await.unwrap();
+        assert!(rules.iter().any(|r| r.id == rule.id));
+
+        let result = client.delete_category_rule(&api_key, &rule.id).await;
+        assert!(result.is_ok());
+    }
 }
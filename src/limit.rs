@@ -0,0 +1,359 @@
+//! Client-side rate limiting and concurrency control.
+//!
+//! `ClientBuilder::rate_limit` spaces requests with a token bucket and
+//! `ClientBuilder::max_in_flight` caps how many requests are sent at once.
+//! Requests over either limit wait in line; `Client::limit_metrics` reports
+//! how long they waited.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+use crate::middleware::{Middleware, Next};
+use crate::transport::{HttpRequest, TransportFuture};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// `requests` every `per`, with bursts of up to `requests`.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        let requests = requests.max(1);
+        Self {
+            requests,
+            per,
+            burst: requests,
+        }
+    }
+
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// How many requests may be sent back to back after the client was idle.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    fn interval(&self) -> Duration {
+        self.per / self.requests
+    }
+}
+
+/// Token bucket where requests reserve a token ahead of time, so waiting
+/// requests are served in order without polling.
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// Takes a token and returns how long to wait until it is available.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let interval = self.limit.interval().as_secs_f64();
+        let refilled = if interval > 0.0 {
+            now.saturating_duration_since(self.updated_at).as_secs_f64() / interval
+        } else {
+            f64::INFINITY
+        };
+        self.tokens = (self.tokens + refilled).min(self.limit.burst as f64);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens * interval)
+        }
+    }
+}
+
+/// Counters of the requests that went through the limits.
+#[derive(Debug, Default)]
+pub struct LimitMetrics {
+    requests: AtomicU64,
+    queued: AtomicU64,
+    in_flight: AtomicU64,
+    total_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LimitMetricsSnapshot {
+    /// Requests sent since the client was built.
+    pub requests: u64,
+    /// Requests waiting for a token or an in-flight slot right now.
+    pub queued: u64,
+    pub in_flight: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl LimitMetricsSnapshot {
+    pub fn mean_wait(&self) -> Duration {
+        match self.requests {
+            0 => Duration::ZERO,
+            requests => self.total_wait / requests as u32,
+        }
+    }
+}
+
+impl LimitMetrics {
+    pub fn snapshot(&self) -> LimitMetricsSnapshot {
+        LimitMetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            total_wait: Duration::from_micros(self.total_wait_micros.load(Ordering::Relaxed)),
+            max_wait: Duration::from_micros(self.max_wait_micros.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let micros = wait.as_micros() as u64;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_wait_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+}
+
+/// Middleware holding requests back until the rate limit and the in-flight
+/// cap let them through.
+pub struct Limiter {
+    bucket: Option<Mutex<TokenBucket>>,
+    semaphore: Option<Semaphore>,
+    metrics: Arc<LimitMetrics>,
+}
+
+impl Limiter {
+    pub fn new(
+        rate_limit: Option<RateLimit>,
+        max_in_flight: Option<usize>,
+        metrics: Arc<LimitMetrics>,
+    ) -> Self {
+        Self {
+            bucket: rate_limit.map(|limit| Mutex::new(TokenBucket::new(limit))),
+            semaphore: max_in_flight.map(|max| Semaphore::new(max.max(1))),
+            metrics,
+        }
+    }
+}
+
+impl Middleware for Limiter {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let queued_at = Instant::now();
+            let queued = Gauge::increment(&self.metrics.queued);
+
+            let _permit = match &self.semaphore {
+                Some(semaphore) => Some(semaphore.acquire().await?),
+                None => None,
+            };
+            if let Some(bucket) = &self.bucket {
+                let wait = bucket.lock().unwrap().reserve(Instant::now());
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+
+            drop(queued);
+            self.metrics.record_wait(queued_at.elapsed());
+            let _in_flight = Gauge::increment(&self.metrics.in_flight);
+            next.run(request).await
+        })
+    }
+}
+
+/// Counts a request in `queued` or `in_flight` until dropped, so requests
+/// whose future is dropped midway aren't counted forever.
+struct Gauge<'a>(&'a AtomicU64);
+
+impl<'a> Gauge<'a> {
+    fn increment(counter: &'a AtomicU64) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::{json_response, HttpTransport, MemoryTransport};
+
+    fn request() -> HttpRequest {
+        http::Request::builder()
+            .uri("https://api.pluggy.ai/items/1")
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    async fn send(limiter: Arc<Limiter>, transport: &dyn HttpTransport) {
+        let middlewares: [Arc<dyn Middleware>; 1] = [limiter];
+        Next::new(&middlewares, transport)
+            .run(request())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn can_reserve_tokens() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(RateLimit::per_second(10).burst(2));
+        bucket.updated_at = now;
+
+        assert_eq!(bucket.reserve(now), Duration::ZERO);
+        assert_eq!(bucket.reserve(now), Duration::ZERO);
+        assert_eq!(bucket.reserve(now), Duration::from_millis(100));
+        assert_eq!(bucket.reserve(now), Duration::from_millis(200));
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn can_rate_limit_requests() {
+        let metrics = Arc::new(LimitMetrics::default());
+        let limiter = Arc::new(Limiter::new(
+            Some(RateLimit::per_second(10).burst(1)),
+            None,
+            metrics.clone(),
+        ));
+        let transport = MemoryTransport::new(|_| json_response(http::StatusCode::OK, &json!({})));
+
+        let started_at = Instant::now();
+        for _ in 0..5 {
+            send(limiter.clone(), &transport).await;
+        }
+
+        assert!(started_at.elapsed() >= Duration::from_millis(400));
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.requests, 5);
+        assert_eq!(snapshot.queued, 0);
+        assert!(snapshot.max_wait >= Duration::from_millis(90));
+        assert!(snapshot.mean_wait() > Duration::ZERO);
+    }
+
+    /// Answers after yielding to the runtime, keeping track of how many
+    /// requests it held at once.
+    #[derive(Default)]
+    struct SlowTransport {
+        in_flight: AtomicU64,
+        max_in_flight: AtomicU64,
+    }
+
+    impl HttpTransport for SlowTransport {
+        fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+            Box::pin(async move {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                Ok(json_response(http::StatusCode::OK, &json!({})))
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn can_cap_requests_in_flight() {
+        let metrics = Arc::new(LimitMetrics::default());
+        let limiter = Arc::new(Limiter::new(None, Some(2), metrics.clone()));
+        let transport = Arc::new(SlowTransport::default());
+
+        let sends: Vec<_> = (0..10)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let transport = transport.clone();
+                tokio::spawn(async move { send(limiter, &*transport).await })
+            })
+            .collect();
+        for send in sends {
+            send.await.unwrap();
+        }
+
+        assert_eq!(transport.max_in_flight.load(Ordering::SeqCst), 2);
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.requests, 10);
+        assert_eq!(snapshot.in_flight, 0);
+        assert!(snapshot.max_wait >= Duration::from_millis(40));
+    }
+
+    /// Never answers.
+    struct PendingTransport;
+
+    impl HttpTransport for PendingTransport {
+        fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn can_drop_waiting_requests() {
+        let metrics = Arc::new(LimitMetrics::default());
+        let limiter = Arc::new(Limiter::new(None, Some(1), metrics.clone()));
+        let transport = MemoryTransport::new(|_| json_response(http::StatusCode::OK, &json!({})));
+
+        let first = tokio::spawn(send(limiter.clone(), &PendingTransport));
+        tokio::task::yield_now().await;
+        assert_eq!(metrics.snapshot().in_flight, 1);
+
+        let second =
+            tokio::time::timeout(Duration::from_millis(10), send(limiter, &transport)).await;
+        assert!(second.is_err());
+        assert_eq!(metrics.snapshot().queued, 0);
+
+        first.abort();
+        assert!(first.await.is_err());
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.in_flight, 0);
+        assert_eq!(snapshot.queued, 0);
+    }
+
+    #[tokio::test]
+    async fn can_report_client_limit_metrics() {
+        let client = crate::Client::builder("id".to_string(), "secret".to_string())
+            .transport(MemoryTransport::new(|_| {
+                json_response(http::StatusCode::OK, &json!({ "apiKey": "key" }))
+            }))
+            .rate_limit(RateLimit::per_minute(600))
+            .max_in_flight(4)
+            .build();
+
+        client.create_api_key().await.unwrap();
+        client.create_api_key().await.unwrap();
+
+        let snapshot = client.limit_metrics();
+        assert_eq!(snapshot.requests, 2);
+        assert_eq!(snapshot.queued, 0);
+    }
+}
@@ -0,0 +1,182 @@
+//! Middleware around the requests `Client` sends.
+//!
+//! Every request goes through the client middleware chain before reaching
+//! the transport. A middleware may change the request, answer it without
+//! calling the next one, or look at the response on its way back:
+//!
+//! ```
+//! use pluggy::middleware::{Middleware, Next};
+//! use pluggy::transport::{HttpRequest, TransportFuture};
+//!
+//! struct UserAgent;
+//!
+//! impl Middleware for UserAgent {
+//!     fn handle<'a>(&'a self, mut request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+//!         request
+//!             .headers_mut()
+//!             .insert("User-Agent", "nightly-refresh/1.0".parse().unwrap());
+//!         next.run(request)
+//!     }
+//! }
+//!
+//! let client = pluggy::Client::builder("id".to_string(), "secret".to_string())
+//!     .middleware(UserAgent)
+//!     .build();
+//! ```
+//!
+//! Middleware added with `ClientBuilder::middleware` runs in the order it
+//! was added, after tracing and metrics and before retries, rate limiting and
+//! cassettes, so that a retried request goes through it once.
+
+use std::sync::Arc;
+
+use http::HeaderMap;
+
+use crate::transport::{HttpRequest, HttpTransport, TransportFuture};
+
+pub trait Middleware: Send + Sync {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a>;
+}
+
+impl<T: Middleware + ?Sized> Middleware for Arc<T> {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        (**self).handle(request, next)
+    }
+}
+
+/// The rest of the chain, ending in the transport. It can be run more than
+/// once, to retry a request.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    transport: &'a dyn HttpTransport,
+}
+
+impl<'a> Next<'a> {
+    pub fn new(middlewares: &'a [Arc<dyn Middleware>], transport: &'a dyn HttpTransport) -> Self {
+        Self {
+            middlewares,
+            transport,
+        }
+    }
+
+    pub fn run(self, request: HttpRequest) -> TransportFuture<'a> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => middleware.handle(
+                request,
+                Next {
+                    middlewares,
+                    transport: self.transport,
+                },
+            ),
+            None => self.transport.send(request),
+        }
+    }
+}
+
+/// Adds headers to every request, keeping the ones the request already has.
+#[derive(Debug, Clone, Default)]
+pub struct SetHeaders {
+    headers: HeaderMap,
+}
+
+impl SetHeaders {
+    pub fn new(headers: HeaderMap) -> Self {
+        Self { headers }
+    }
+}
+
+impl Middleware for SetHeaders {
+    fn handle<'a>(&'a self, mut request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        for (name, value) in &self.headers {
+            if !request.headers().contains_key(name) {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+
+        next.run(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use http::StatusCode;
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::{json_response, MemoryTransport};
+    use crate::Client;
+
+    /// Answers requests to a URI it has already seen with the body it got the
+    /// first time, without calling the rest of the chain.
+    #[derive(Default)]
+    struct Cache {
+        responses: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl Middleware for Cache {
+        fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+            Box::pin(async move {
+                let uri = request.uri().to_string();
+                let cached = self
+                    .responses
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|(cached, _)| *cached == uri)
+                    .map(|(_, body)| body.clone());
+                if let Some(body) = cached {
+                    return Ok(http::Response::new(body));
+                }
+
+                let response = next.run(request).await?;
+                self.responses
+                    .lock()
+                    .unwrap()
+                    .push((uri, response.body().clone()));
+                Ok(response)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn can_run_middleware_in_order() {
+        let transport = Arc::new(MemoryTransport::new(|request| {
+            json_response(
+                StatusCode::OK,
+                &json!({ "apiKey": request.headers["X-Tenant"].to_str().unwrap() }),
+            )
+        }));
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Tenant", "first".parse().unwrap());
+        let mut overridden = HeaderMap::new();
+        overridden.insert("X-Tenant", "second".parse().unwrap());
+
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .transport(transport.clone())
+            .middleware(SetHeaders::new(headers))
+            .middleware(SetHeaders::new(overridden))
+            .build();
+
+        assert_eq!(client.create_api_key().await.unwrap(), "first");
+    }
+
+    #[tokio::test]
+    async fn can_short_circuit_requests() {
+        let transport = Arc::new(MemoryTransport::new(|_| {
+            json_response(StatusCode::OK, &json!({ "apiKey": "key" }))
+        }));
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .transport(transport.clone())
+            .middleware(Cache::default())
+            .build();
+
+        for _ in 0..3 {
+            assert_eq!(client.create_api_key().await.unwrap(), "key");
+        }
+
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
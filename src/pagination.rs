@@ -0,0 +1,282 @@
+//! Lazy iteration over paginated list endpoints.
+//!
+//! `Paginated` is a `Stream` of the results of every page of a list endpoint.
+//! Pages are requested as the stream is consumed, with up to
+//! `PageOptions::prefetch` pages requested ahead:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use futures_util::TryStreamExt;
+//! use pluggy::pagination::PageOptions;
+//! use pluggy::Client;
+//!
+//! let (client, api_key) = Client::new_from_env_with_api_key().await?;
+//! let mut categories = client.stream_categories(&api_key, PageOptions::default())?;
+//! while let Some(category) = categories.try_next().await? {
+//!     println!("{}", category.description);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::stream::{self, FuturesOrdered, Stream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use crate::transport::TransportError;
+use crate::{authenticated_request_builder, Client, PageResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageOptions {
+    /// Results per page, or the API default.
+    pub page_size: Option<u32>,
+    /// Pages requested ahead of the one being consumed.
+    pub prefetch: usize,
+}
+
+impl PageOptions {
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    pub fn prefetch(mut self, prefetch: usize) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        Self {
+            page_size: None,
+            prefetch: 1,
+        }
+    }
+}
+
+type PageFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<PageResponse<T>, TransportError>> + Send + 'a>>;
+
+type PageFilter<'a, T> = Box<dyn Fn(&T) -> bool + Send + 'a>;
+
+/// Results of every page of a list endpoint, in order. The stream ends after
+/// the first error.
+pub struct Paginated<'a, T> {
+    client: &'a Client,
+    api_key: &'a str,
+    url: Url,
+    options: PageOptions,
+    filter: Option<PageFilter<'a, T>>,
+    results: VecDeque<T>,
+    pages: FuturesOrdered<PageFuture<'a, T>>,
+    next_page: i32,
+    total_pages: Option<i32>,
+    failed: bool,
+}
+
+// Results are never pinned, only the page futures, which are boxed.
+impl<T> Unpin for Paginated<'_, T> {}
+
+impl<'a, T: DeserializeOwned + 'a> Paginated<'a, T> {
+    pub(crate) fn new(
+        client: &'a Client,
+        api_key: &'a str,
+        url: Url,
+        options: PageOptions,
+    ) -> Self {
+        Self {
+            client,
+            api_key,
+            url,
+            options,
+            filter: None,
+            results: VecDeque::new(),
+            pages: FuturesOrdered::new(),
+            next_page: 1,
+            total_pages: None,
+            failed: false,
+        }
+    }
+
+    /// Skips the results `filter` rejects, for filters the API doesn't
+    /// support.
+    pub(crate) fn with_filter(mut self, filter: impl Fn(&T) -> bool + Send + 'a) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Fetches every page into a `Vec`, for lists known to be small.
+    pub async fn collect_all(self) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        self.collect_all_send()
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)
+    }
+
+    /// Like `collect_all`, failing with an error that can be sent across
+    /// threads.
+    pub(crate) async fn collect_all_send(mut self) -> Result<Vec<T>, TransportError> {
+        stream::poll_fn(|cx| self.poll_result(cx))
+            .try_collect()
+            .await
+    }
+
+    /// Next result, keeping the transport's `Send` error.
+    fn poll_result(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, TransportError>>> {
+        loop {
+            if let Some(result) = self.results.pop_front() {
+                return Poll::Ready(Some(Ok(result)));
+            }
+
+            self.request_pages();
+            match self.pages.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(Err(error))) => {
+                    self.failed = true;
+                    self.pages = FuturesOrdered::new();
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Ready(Some(Ok(page))) => {
+                    self.total_pages = Some(page.total_pages);
+                    let filter = &self.filter;
+                    let results = page
+                        .results
+                        .into_iter()
+                        .filter(|result| filter.as_ref().is_none_or(|filter| filter(result)));
+                    self.results.extend(results);
+                }
+            }
+        }
+    }
+
+    /// Requests the first page, then up to `prefetch` pages past the one
+    /// being consumed once the page count is known.
+    fn request_pages(&mut self) {
+        let max_pending = match self.total_pages {
+            Some(_) => 1 + self.options.prefetch,
+            None => 1,
+        };
+
+        while !self.failed
+            && self.pages.len() < max_pending
+            && self
+                .total_pages
+                .is_none_or(|total_pages| self.next_page <= total_pages)
+            && (self.total_pages.is_some() || self.next_page == 1)
+        {
+            let mut url = self.url.clone();
+            url.query_pairs_mut()
+                .append_pair("page", &self.next_page.to_string());
+            if let Some(page_size) = self.options.page_size {
+                url.query_pairs_mut()
+                    .append_pair("pageSize", &page_size.to_string());
+            }
+
+            self.pages
+                .push_back(Box::pin(fetch_page(self.client, self.api_key, url)));
+            self.next_page += 1;
+        }
+    }
+}
+
+async fn fetch_page<T: DeserializeOwned>(
+    client: &Client,
+    api_key: &str,
+    url: Url,
+) -> Result<PageResponse<T>, TransportError> {
+    let request =
+        authenticated_request_builder(http::Method::GET, &url, api_key).body(Vec::new())?;
+    let response = client.dispatch(request).await?;
+
+    let json: PageResponse<T> = serde_json::from_slice(response.body())?;
+
+    Ok(json)
+}
+
+impl<'a, T: DeserializeOwned + 'a> Stream for Paginated<'a, T> {
+    type Item = Result<T, Box<dyn std::error::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_result(cx).map(|result| {
+            result.map(|result| result.map_err(|error| error as Box<dyn std::error::Error>))
+        })
+    }
+}
+
+#[cfg(all(test, feature = "hyper"))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::MockServer;
+    use crate::{ConnectorFilter, ItemId, Uuid};
+
+    fn assert_send<T: Send>(_: T) {}
+
+    #[tokio::test]
+    async fn can_stream_every_page() {
+        let server = MockServer::start().await;
+        for id in 0..45 {
+            server.add_category(json!({
+                "id": format!("9{:04}", id),
+                "description": format!("Category {}", id),
+                "parentId": null,
+                "parentDescription": null,
+            }));
+        }
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let all = client.get_categories(&api_key).await.unwrap();
+        let options = PageOptions::default().page_size(10).prefetch(2);
+        let streamed: Vec<_> = client
+            .stream_categories(&api_key, options)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(all.len() > 45);
+        assert_eq!(
+            streamed.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            all.iter().map(|c| &c.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn can_end_stream_after_error() {
+        let server = MockServer::start().await;
+        let client = server.client();
+
+        let mut webhooks = client
+            .stream_webhooks("invalid-api-key", PageOptions::default())
+            .unwrap();
+
+        assert!(webhooks.next().await.unwrap().is_err());
+        assert!(webhooks.next().await.is_none());
+    }
+
+    #[test]
+    fn can_send_list_futures_across_threads() {
+        let client = Client::new("client_id".to_string(), "client_secret".to_string());
+        let filter = ConnectorFilter::default();
+
+        assert_send(client.get_connectors("api_key", true));
+        assert_send(client.search_connectors("api_key", &filter));
+        assert_send(client.get_categories("api_key"));
+        assert_send(client.get_category_rules("api_key"));
+        assert_send(client.get_webhooks("api_key"));
+        assert_send(client.get_smart_transfer_preauthorizations("api_key"));
+        assert_send(client.get_consents("api_key", ItemId(Uuid::nil())));
+        assert_send(
+            client
+                .stream_connectors("api_key", &filter, PageOptions::default())
+                .unwrap(),
+        );
+    }
+}
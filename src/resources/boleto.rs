@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::{ConnectorId, Date, Money, Timestamp};
+
+api_enum! {
+    pub enum BoletoStatus {
+        Open = "OPEN",
+        Paid = "PAID",
+        Overdue = "OVERDUE",
+        Cancelled = "CANCELLED",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoletoConnection {
+    pub id: String,
+    pub connector_id: ConnectorId,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoletoPayer {
+    pub tax_number: String,
+    pub name: String,
+    pub address_street: Option<String>,
+    pub address_city: Option<String>,
+    pub address_state: Option<String>,
+    pub address_zip_code: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Boleto {
+    pub id: String,
+    pub boleto_connection_id: String,
+    pub status: BoletoStatus,
+    pub seu_numero: Option<String>,
+    pub nosso_numero: Option<String>,
+    pub digitable_line: Option<String>,
+    pub barcode: Option<String>,
+    #[serde(with = "super::money::brl")]
+    pub amount: Money,
+    #[serde(with = "super::money::brl::option")]
+    pub amount_paid: Option<Money>,
+    pub due_date: Date,
+    pub payer: BoletoPayer,
+    /// Fine percentage charged once the boleto is overdue.
+    #[serde(with = "super::money::number::option", default)]
+    pub fine: Option<Decimal>,
+    /// Monthly interest percentage charged once the boleto is overdue.
+    #[serde(with = "super::money::number::option", default)]
+    pub interest: Option<Decimal>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// Boleto to be issued through a boleto connection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewBoleto {
+    pub seu_numero: String,
+    /// Must be in reais.
+    #[serde(with = "super::money::brl")]
+    pub amount: Money,
+    pub due_date: Date,
+    pub payer: BoletoPayer,
+    #[serde(with = "super::money::number::option", default)]
+    pub fine: Option<Decimal>,
+    #[serde(with = "super::money::number::option", default)]
+    pub interest: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBoletoConnectionRequest<'a> {
+    pub connector_id: ConnectorId,
+    pub credentials: &'a HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueBoletoRequest<'a> {
+    pub boleto_connection_id: &'a str,
+    pub boleto: &'a NewBoleto,
+}
+
+/// Kind of boleto, as identified by the first digit of its barcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoletoKind {
+    /// Bank collection boleto ("boleto bancário"), 47 digit line.
+    Bank,
+    /// Utility and tax bills ("arrecadação/convênio"), 48 digit line.
+    Collection,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoletoParseError {
+    InvalidLength(usize),
+    InvalidCharacter(char),
+    InvalidCheckDigit,
+}
+
+impl fmt::Display for BoletoParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(length) => write!(f, "invalid boleto length: {}", length),
+            Self::InvalidCharacter(c) => write!(f, "invalid boleto character: {:?}", c),
+            Self::InvalidCheckDigit => write!(f, "invalid boleto check digit"),
+        }
+    }
+}
+
+impl std::error::Error for BoletoParseError {}
+
+/// Number of days between the unix epoch and 2025-02-22, the base date of
+/// the due date factor since it rolled over from 9999 back to 1000.
+const DUE_DATE_FACTOR_BASE_DAYS: i64 = 20141;
+const DUE_DATE_FACTOR_BASE: i64 = 1000;
+
+/// A boleto barcode: the 44 digits encoded in the bars.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Barcode(String);
+
+/// A boleto digitable line ("linha digitável"): the 47 or 48 digits typed by
+/// the user, each block carrying its own check digit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DigitableLine(String);
+
+impl Barcode {
+    pub fn parse(value: &str) -> Result<Self, BoletoParseError> {
+        let digits = normalize(value)?;
+        if digits.len() != 44 {
+            return Err(BoletoParseError::InvalidLength(digits.len()));
+        }
+
+        let barcode = Self(digits);
+        let general_digit = barcode.general_check_digit();
+        let without_digit = format!(
+            "{}{}",
+            &barcode.0[..general_digit],
+            &barcode.0[general_digit + 1..]
+        );
+        let expected = match barcode.kind() {
+            BoletoKind::Bank => mod11_bank(&without_digit),
+            BoletoKind::Collection => collection_check_digit(&barcode.0, &without_digit)?,
+        };
+        if digit_at(&barcode.0, general_digit) != expected {
+            return Err(BoletoParseError::InvalidCheckDigit);
+        }
+
+        Ok(barcode)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn kind(&self) -> BoletoKind {
+        if self.0.starts_with('8') {
+            BoletoKind::Collection
+        } else {
+            BoletoKind::Bank
+        }
+    }
+
+    /// Three digit code of the issuing bank, for bank boletos.
+    pub fn bank_code(&self) -> Option<&str> {
+        match self.kind() {
+            BoletoKind::Bank => Some(&self.0[..3]),
+            BoletoKind::Collection => None,
+        }
+    }
+
+    /// Amount in cents, when the barcode carries one.
+    pub fn amount_cents(&self) -> Option<u64> {
+        let amount = match self.kind() {
+            BoletoKind::Bank => &self.0[9..19],
+            BoletoKind::Collection if matches!(&self.0[2..3], "6" | "8") => &self.0[4..15],
+            BoletoKind::Collection => return None,
+        };
+
+        amount.parse().ok().filter(|cents| *cents > 0)
+    }
+
+    pub fn amount(&self) -> Option<Money> {
+        self.amount_cents()
+            .map(|cents| Money::brl(Decimal::new(cents as i64, 2)))
+    }
+
+    /// Due date of bank boletos that carry a due date factor.
+    pub fn due_date(&self) -> Option<Date> {
+        if self.kind() != BoletoKind::Bank {
+            return None;
+        }
+
+        let factor: i64 = self.0[5..9].parse().ok()?;
+        if factor == 0 {
+            return None;
+        }
+
+        let (year, month, day) =
+            civil_from_days(DUE_DATE_FACTOR_BASE_DAYS + factor - DUE_DATE_FACTOR_BASE);
+        #[cfg(feature = "chrono")]
+        return Date::from_ymd_opt(year as i32, month, day);
+        #[cfg(not(feature = "chrono"))]
+        return Some(format!("{:04}-{:02}-{:02}", year, month, day));
+    }
+
+    pub fn to_digitable_line(&self) -> DigitableLine {
+        let code = &self.0;
+        let line = match self.kind() {
+            BoletoKind::Bank => {
+                let free_field = &code[19..44];
+                let block1 = format!("{}{}", &code[0..4], &free_field[0..5]);
+                let block2 = &free_field[5..15];
+                let block3 = &free_field[15..25];
+                format!(
+                    "{}{}{}{}{}{}{}{}",
+                    block1,
+                    mod10(&block1),
+                    block2,
+                    mod10(block2),
+                    block3,
+                    mod10(block3),
+                    &code[4..5],
+                    &code[5..19]
+                )
+            }
+            BoletoKind::Collection => (0..4)
+                .map(|i| {
+                    let block = &code[i * 11..(i + 1) * 11];
+                    let digit = collection_check_digit(code, block).unwrap_or_default();
+                    format!("{}{}", block, digit)
+                })
+                .collect(),
+        };
+
+        DigitableLine(line)
+    }
+
+    fn general_check_digit(&self) -> usize {
+        match self.kind() {
+            BoletoKind::Bank => 4,
+            BoletoKind::Collection => 3,
+        }
+    }
+}
+
+impl DigitableLine {
+    pub fn parse(value: &str) -> Result<Self, BoletoParseError> {
+        let digits = normalize(value)?;
+
+        let line = match digits.len() {
+            47 => {
+                let blocks = [
+                    (&digits[0..9], 9),
+                    (&digits[10..20], 20),
+                    (&digits[21..31], 31),
+                ];
+                for (block, check_digit) in blocks {
+                    if digit_at(&digits, check_digit) != mod10(block) {
+                        return Err(BoletoParseError::InvalidCheckDigit);
+                    }
+                }
+
+                Self(digits)
+            }
+            48 if digits.starts_with('8') => {
+                for i in 0..4 {
+                    let block = &digits[i * 12..i * 12 + 11];
+                    let expected = collection_check_digit(&digits, block)?;
+                    if digit_at(&digits, i * 12 + 11) != expected {
+                        return Err(BoletoParseError::InvalidCheckDigit);
+                    }
+                }
+
+                Self(digits)
+            }
+            length => return Err(BoletoParseError::InvalidLength(length)),
+        };
+
+        Barcode::parse(line.to_barcode().as_str())?;
+
+        Ok(line)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn kind(&self) -> BoletoKind {
+        if self.0.len() == 48 {
+            BoletoKind::Collection
+        } else {
+            BoletoKind::Bank
+        }
+    }
+
+    pub fn to_barcode(&self) -> Barcode {
+        let line = &self.0;
+        let barcode = match self.kind() {
+            BoletoKind::Bank => format!(
+                "{}{}{}{}{}",
+                &line[0..4],
+                &line[32..47],
+                &line[4..9],
+                &line[10..20],
+                &line[21..31]
+            ),
+            BoletoKind::Collection => (0..4).map(|i| &line[i * 12..i * 12 + 11]).collect(),
+        };
+
+        Barcode(barcode)
+    }
+}
+
+impl FromStr for Barcode {
+    type Err = BoletoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl FromStr for DigitableLine {
+    type Err = BoletoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Barcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for DigitableLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Strips the dots, spaces and dashes users usually type along the digits.
+fn normalize(value: &str) -> Result<String, BoletoParseError> {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '.' | ' ' | '-'))
+        .map(|c| {
+            if c.is_ascii_digit() {
+                Ok(c)
+            } else {
+                Err(BoletoParseError::InvalidCharacter(c))
+            }
+        })
+        .collect()
+}
+
+fn digit_at(digits: &str, index: usize) -> u32 {
+    u32::from(digits.as_bytes()[index] - b'0')
+}
+
+fn mod10(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let product = u32::from(b - b'0') * if i % 2 == 0 { 2 } else { 1 };
+            product / 10 + product % 10
+        })
+        .sum();
+
+    (10 - sum % 10) % 10
+}
+
+fn mod11_sum(digits: &str) -> u32 {
+    digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| u32::from(b - b'0') * (2 + i as u32 % 8))
+        .sum()
+}
+
+fn mod11_bank(digits: &str) -> u32 {
+    match 11 - mod11_sum(digits) % 11 {
+        0 | 10 | 11 => 1,
+        digit => digit,
+    }
+}
+
+fn mod11_collection(digits: &str) -> u32 {
+    match mod11_sum(digits) % 11 {
+        0 | 1 => 0,
+        10 => 1,
+        remainder => 11 - remainder,
+    }
+}
+
+/// Collection boletos pick the check digit algorithm from their third digit,
+/// the value identifier.
+fn collection_check_digit(code: &str, digits: &str) -> Result<u32, BoletoParseError> {
+    match &code[2..3] {
+        "6" | "7" => Ok(mod10(digits)),
+        "8" | "9" => Ok(mod11_collection(digits)),
+        _ => Err(BoletoParseError::InvalidCheckDigit),
+    }
+}
+
+/// Converts days since the unix epoch into a (year, month, day) civil date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BANK_LINE: &str = "00190.00009 01234.567806 00000.000174 7 10000000012345";
+    const BANK_BARCODE: &str = "00197100000000123450000001234567800000000017";
+    const COLLECTION_LINE: &str = "816600000011 234501234566 789012345672 890123456786";
+    const COLLECTION_MOD11_LINE: &str = "828700000012234501234567789012345675890123456785";
+
+    #[test]
+    fn can_parse_bank_digitable_line() {
+        let line = DigitableLine::parse(BANK_LINE).unwrap();
+        let barcode = line.to_barcode();
+
+        assert_eq!(line.kind(), BoletoKind::Bank);
+        assert_eq!(barcode.as_str(), BANK_BARCODE);
+        assert_eq!(barcode.bank_code(), Some("001"));
+        assert_eq!(barcode.amount_cents(), Some(12345));
+        assert_eq!(barcode.amount(), Some(Money::brl(Decimal::new(12345, 2))));
+        assert_eq!(
+            barcode.due_date().map(|date| date.to_string()).as_deref(),
+            Some("2025-02-22")
+        );
+        assert_eq!(barcode.to_digitable_line(), line);
+    }
+
+    #[test]
+    fn can_parse_collection_digitable_line() {
+        for value in [COLLECTION_LINE, COLLECTION_MOD11_LINE] {
+            let line = DigitableLine::parse(value).unwrap();
+            let barcode = line.to_barcode();
+
+            assert_eq!(line.kind(), BoletoKind::Collection);
+            assert_eq!(barcode.amount_cents(), Some(12345));
+            assert_eq!(barcode.due_date(), None);
+            assert_eq!(barcode.to_digitable_line(), line);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_digitable_lines() {
+        let tampered = BANK_LINE.replace("12345", "12346");
+        assert_eq!(
+            DigitableLine::parse(&tampered),
+            Err(BoletoParseError::InvalidCheckDigit)
+        );
+        assert_eq!(
+            DigitableLine::parse("0019000009"),
+            Err(BoletoParseError::InvalidLength(10))
+        );
+        assert_eq!(
+            DigitableLine::parse("0019x"),
+            Err(BoletoParseError::InvalidCharacter('x'))
+        );
+        assert_eq!(
+            Barcode::parse("00198100000000123450000001234567800000000017"),
+            Err(BoletoParseError::InvalidCheckDigit)
+        );
+    }
+}
@@ -1,10 +1,156 @@
-use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+use serde::{Deserialize, Serialize};
+
+use super::{CategoryId, Timestamp};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Category {
-    pub id: String,
+    pub id: CategoryId,
     pub description: String,
-    pub parent_id: Option<String>,
+    pub parent_id: Option<CategoryId>,
     pub parent_description: Option<String>,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryRule {
+    pub id: String,
+    pub description: String,
+    pub category_id: CategoryId,
+    pub created_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCategoryRuleRequest<'a> {
+    pub description: &'a str,
+    pub category_id: &'a CategoryId,
+}
+
+/// Categories indexed by id, so a category can be walked up to its root
+/// through `Category::parent_id`.
+#[derive(Debug, Default)]
+pub struct CategoryTree {
+    categories: HashMap<CategoryId, Category>,
+}
+
+impl CategoryTree {
+    pub fn new(categories: Vec<Category>) -> Self {
+        categories.into_iter().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    pub fn get(&self, category_id: &CategoryId) -> Option<&Category> {
+        self.categories.get(category_id)
+    }
+
+    pub fn parent(&self, category_id: &CategoryId) -> Option<&Category> {
+        self.get(category_id)?
+            .parent_id
+            .as_ref()
+            .and_then(|parent_id| self.get(parent_id))
+    }
+
+    pub fn children(&self, category_id: &CategoryId) -> Vec<&Category> {
+        self.categories
+            .values()
+            .filter(|category| category.parent_id.as_ref() == Some(category_id))
+            .collect()
+    }
+
+    /// Walks from the given category up to its root, starting with the
+    /// category itself.
+    pub fn ancestors<'a>(
+        &'a self,
+        category_id: &CategoryId,
+    ) -> impl Iterator<Item = &'a Category> + 'a {
+        let mut next = self.get(category_id);
+        // Bounded by the number of categories so a malformed cycle can't loop forever.
+        let mut remaining = self.categories.len();
+
+        std::iter::from_fn(move || {
+            let category = next.filter(|_| remaining > 0)?;
+            remaining -= 1;
+            next = category
+                .parent_id
+                .as_ref()
+                .and_then(|parent_id| self.get(parent_id));
+            Some(category)
+        })
+    }
+
+    pub fn root(&self, category_id: &CategoryId) -> Option<&Category> {
+        self.ancestors(category_id).last()
+    }
+
+    pub fn roots(&self) -> Vec<&Category> {
+        self.categories
+            .values()
+            .filter(|category| category.parent_id.is_none())
+            .collect()
+    }
+}
+
+impl FromIterator<Category> for CategoryTree {
+    fn from_iter<I: IntoIterator<Item = Category>>(iter: I) -> Self {
+        Self {
+            categories: iter
+                .into_iter()
+                .map(|category| (category.id.clone(), category))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(id: &str, parent_id: Option<&str>) -> Category {
+        Category {
+            id: id.into(),
+            description: id.to_string(),
+            parent_id: parent_id.map(CategoryId::from),
+            parent_description: None,
+        }
+    }
+
+    #[test]
+    fn can_walk_category_tree_to_root() {
+        let tree = CategoryTree::new(vec![
+            category("01000000", None),
+            category("01010000", Some("01000000")),
+            category("01010001", Some("01010000")),
+            category("02000000", None),
+        ]);
+
+        let id = |id: &str| CategoryId::from(id);
+        let path: Vec<&str> = tree
+            .ancestors(&id("01010001"))
+            .map(|category| category.id.as_str())
+            .collect();
+
+        assert_eq!(path, vec!["01010001", "01010000", "01000000"]);
+        assert_eq!(tree.root(&id("01010001")).unwrap().id, id("01000000"));
+        assert_eq!(tree.parent(&id("01010000")).unwrap().id, id("01000000"));
+        assert_eq!(tree.children(&id("01000000")).len(), 1);
+        assert_eq!(tree.roots().len(), 2);
+        assert!(tree.root(&id("99999999")).is_none());
+    }
+
+    #[test]
+    fn stops_walking_on_cycles() {
+        let tree = CategoryTree::new(vec![category("a", Some("b")), category("b", Some("a"))]);
+
+        assert_eq!(tree.ancestors(&CategoryId::from("a")).count(), 2);
+    }
+}
@@ -1,43 +1,48 @@
-use serde::Deserialize;
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ConnectorType {
-    PersonalBank,
-    BusinessBank,
-    Invoice,
-    Investment,
-    Telecommunication,
-    DigitalEconomy,
-    PaymentAccount,
-    Other,
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{ConnectorId, Timestamp};
+
+api_enum! {
+    pub enum ConnectorType {
+        PersonalBank = "PERSONAL_BANK",
+        BusinessBank = "BUSINESS_BANK",
+        Invoice = "INVOICE",
+        Investment = "INVESTMENT",
+        Telecommunication = "TELECOMMUNICATION",
+        DigitalEconomy = "DIGITAL_ECONOMY",
+        PaymentAccount = "PAYMENT_ACCOUNT",
+        Other = "OTHER",
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub enum Country {
-    AR,
-    BR,
+api_enum! {
+    pub enum Country {
+        AR = "AR",
+        BR = "BR",
+        MX = "MX",
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum CredentialType {
-    Number,
-    Password,
-    Text,
-    Image,
-    Select,
-    #[serde(rename = "ethaddress")]
-    EthAddress,
+api_enum! {
+    pub enum CredentialType {
+        Number = "number",
+        Password = "password",
+        Text = "text",
+        Image = "image",
+        Select = "select",
+        EthAddress = "ethaddress",
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CredentialSelectOption {
     pub label: String,
     pub value: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ConnectorCredential {
     pub label: String,
     pub name: String,
@@ -52,49 +57,49 @@ pub struct ConnectorCredential {
     pub placeholder: Option<String>,
     pub optional: Option<bool>,
     pub instructions: Option<String>,
-    pub expires_at: Option<String>, // date
+    pub expires_at: Option<Timestamp>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ConnectorStatus {
-    Online,
-    Offline,
-    Unstable,
+api_enum! {
+    pub enum ConnectorStatus {
+        Online = "ONLINE",
+        Offline = "OFFLINE",
+        Unstable = "UNSTABLE",
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ConnectorStage {
-    Beta,
+api_enum! {
+    pub enum ConnectorStage {
+        Beta = "BETA",
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConnectorHealth {
     pub status: ConnectorStatus,
     pub stage: Option<ConnectorStage>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ProductType {
-    Accounts,
-    CreditCards,
-    Transactions,
-    PaymentData,
-    Investments,
-    InvestmentsTransactions,
-    Identity,
-    BrokerageNote,
-    Opportunities,
-    Portfolio,
-    IncomeReports,
+api_enum! {
+    pub enum ProductType {
+        Accounts = "ACCOUNTS",
+        CreditCards = "CREDIT_CARDS",
+        Transactions = "TRANSACTIONS",
+        PaymentData = "PAYMENT_DATA",
+        Investments = "INVESTMENTS",
+        InvestmentsTransactions = "INVESTMENTS_TRANSACTIONS",
+        Identity = "IDENTITY",
+        BrokerageNote = "BROKERAGE_NOTE",
+        Opportunities = "OPPORTUNITIES",
+        Portfolio = "PORTFOLIO",
+        IncomeReports = "INCOME_REPORTS",
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Connector {
-    pub id: i32,
+    pub id: ConnectorId,
     pub name: String,
     pub institution_url: String,
     pub image_url: String,
@@ -110,5 +115,98 @@ pub struct Connector {
     pub health: Option<ConnectorHealth>,
     pub reset_password_url: Option<String>,
     pub products: Vec<ProductType>,
-    pub created_at: String, // date
+    pub is_open_finance: Option<bool>,
+    pub created_at: Timestamp,
+}
+
+/// Filters for `Client::search_connectors`. Name, countries, types, sandbox
+/// and Open Finance are sent to the API; products and health statuses are
+/// applied to the results.
+#[derive(Debug, Default, Clone)]
+pub struct ConnectorFilter {
+    pub name: Option<String>,
+    pub countries: Vec<Country>,
+    pub types: Vec<ConnectorType>,
+    /// Only connectors supporting every listed product.
+    pub products: Vec<ProductType>,
+    pub is_open_finance: Option<bool>,
+    /// Only connectors whose health status is one of these.
+    pub health_statuses: Vec<ConnectorStatus>,
+    pub sandbox: bool,
+}
+
+impl ConnectorFilter {
+    pub fn append_query_pairs(&self, url: &mut Url) -> Result<(), serde_json::Error> {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("sandbox", &self.sandbox.to_string());
+
+        if let Some(name) = &self.name {
+            query.append_pair("name", name);
+        }
+        if !self.countries.is_empty() {
+            query.append_pair("countries", &join_query_values(&self.countries)?);
+        }
+        if !self.types.is_empty() {
+            query.append_pair("types", &join_query_values(&self.types)?);
+        }
+        if let Some(is_open_finance) = self.is_open_finance {
+            query.append_pair("isOpenFinance", &is_open_finance.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Applies the filters the API doesn't support.
+    pub fn matches(&self, connector: &Connector) -> bool {
+        let supports_products = self
+            .products
+            .iter()
+            .all(|product| connector.products.contains(product));
+
+        let has_health_status = self.health_statuses.is_empty()
+            || connector
+                .health
+                .as_ref()
+                .is_some_and(|health| self.health_statuses.contains(&health.status));
+
+        supports_products && has_health_status
+    }
+}
+
+fn join_query_values<T: Serialize>(values: &[T]) -> Result<String, serde_json::Error> {
+    let values = values
+        .iter()
+        .map(|value| {
+            serde_json::to_value(value).map(|value| match value {
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            })
+        })
+        .collect::<Result<Vec<String>, serde_json::Error>>()?;
+
+    Ok(values.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_build_connector_filter_query() {
+        let filter = ConnectorFilter {
+            name: Some("Itaú".to_string()),
+            countries: vec![Country::BR, Country::AR],
+            types: vec![ConnectorType::PersonalBank],
+            products: vec![ProductType::Transactions],
+            is_open_finance: Some(true),
+            ..Default::default()
+        };
+        let mut url = Url::parse("https://api.pluggy.ai/connectors").unwrap();
+        filter.append_query_pairs(&mut url).unwrap();
+
+        assert_eq!(
+            url.query(),
+            Some("sandbox=false&name=Ita%C3%BA&countries=BR%2CAR&types=PERSONAL_BANK&isOpenFinance=true")
+        );
+    }
 }
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ItemId, ProductType, Timestamp};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Consent {
+    pub id: String,
+    pub item_id: ItemId,
+    pub products: Vec<ProductType>,
+    pub open_finance_permissions_granted: Vec<String>,
+    pub created_at: Timestamp,
+    pub expires_at: Option<Timestamp>,
+    pub revoked_at: Option<Timestamp>,
+}
+
+impl Consent {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Products the user authorized, derived from the granted Open Finance
+    /// permissions.
+    pub fn granted_products(&self) -> Vec<ProductType> {
+        let mut products: Vec<ProductType> = Vec::new();
+
+        for permission in &self.open_finance_permissions_granted {
+            for product in permission_products(permission) {
+                if !products.contains(product) {
+                    products.push(product.clone());
+                }
+            }
+        }
+
+        products
+    }
+}
+
+/// Maps an Open Finance permission (e.g. `ACCOUNTS_BALANCES_READ`) to the
+/// products it gives access to. Permissions without a matching product, such
+/// as `RESOURCES_READ` or loans, map to nothing.
+pub fn permission_products(permission: &str) -> &'static [ProductType] {
+    match permission {
+        "ACCOUNTS_READ" | "ACCOUNTS_BALANCES_READ" | "ACCOUNTS_OVERDRAFT_LIMITS_READ" => {
+            &[ProductType::Accounts]
+        }
+        "ACCOUNTS_TRANSACTIONS_READ" => &[ProductType::Transactions],
+        "CREDIT_CARDS_ACCOUNTS_READ"
+        | "CREDIT_CARDS_ACCOUNTS_BILLS_READ"
+        | "CREDIT_CARDS_ACCOUNTS_LIMITS_READ" => &[ProductType::CreditCards],
+        "CREDIT_CARDS_ACCOUNTS_TRANSACTIONS_READ"
+        | "CREDIT_CARDS_ACCOUNTS_BILLS_TRANSACTIONS_READ" => {
+            &[ProductType::CreditCards, ProductType::Transactions]
+        }
+        "CUSTOMERS_PERSONAL_IDENTIFICATIONS_READ"
+        | "CUSTOMERS_PERSONAL_ADITTIONALINFO_READ"
+        | "CUSTOMERS_BUSINESS_IDENTIFICATIONS_READ"
+        | "CUSTOMERS_BUSINESS_ADITTIONALINFO_READ" => &[ProductType::Identity],
+        "BANK_FIXED_INCOMES_READ"
+        | "CREDIT_FIXED_INCOMES_READ"
+        | "VARIABLE_INCOMES_READ"
+        | "TREASURE_TITLES_READ"
+        | "FUNDS_READ" => &[
+            ProductType::Investments,
+            ProductType::InvestmentsTransactions,
+        ],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_map_granted_permissions_to_products() {
+        let consent: Consent = serde_json::from_str(
+            r#"{
+                "id": "3b1bd1c4-4a8e-4a1b-8b4f-0a7e6a2a1c11",
+                "itemId": "e22c7308-7031-47f0-88a3-462f44d96f70",
+                "products": ["ACCOUNTS", "TRANSACTIONS"],
+                "openFinancePermissionsGranted": [
+                    "RESOURCES_READ",
+                    "ACCOUNTS_READ",
+                    "ACCOUNTS_BALANCES_READ",
+                    "ACCOUNTS_TRANSACTIONS_READ"
+                ],
+                "createdAt": "2023-05-02T12:00:00.000Z",
+                "expiresAt": "2024-05-02T12:00:00.000Z",
+                "revokedAt": null
+            }"#,
+        )
+        .unwrap();
+
+        assert!(!consent.is_revoked());
+        assert_eq!(
+            consent.granted_products(),
+            vec![ProductType::Accounts, ProductType::Transactions]
+        );
+    }
+}
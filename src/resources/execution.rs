@@ -1,26 +1,46 @@
 use std::collections::HashMap;
 
-use serde::Deserialize;
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-
-pub enum ExecutionErrorCodes {
-    InvalidCredentials,
-    AlreadyLoggedIn,
-    UnexpectedError,
-    InvalidCredentialsMfa,
-    SiteNotAvailable,
-    AccountLocked,
-    AccountCredentialsReset,
-    ConnectionError,
-    AccountNeedsAction,
-    UserAuthorizationPending,
-    UserAuthorizationNotGranted,
-    UserInputTimeout,
+use serde::{Deserialize, Serialize};
+
+use super::ProductType;
+
+api_enum! {
+    pub enum ExecutionErrorCodes {
+        InvalidCredentials = "INVALID_CREDENTIALS",
+        AlreadyLoggedIn = "ALREADY_LOGGED_IN",
+        UnexpectedError = "UNEXPECTED_ERROR",
+        InvalidCredentialsMfa = "INVALID_CREDENTIALS_MFA",
+        SiteNotAvailable = "SITE_NOT_AVAILABLE",
+        AccountLocked = "ACCOUNT_LOCKED",
+        AccountCredentialsReset = "ACCOUNT_CREDENTIALS_RESET",
+        ConnectionError = "CONNECTION_ERROR",
+        AccountNeedsAction = "ACCOUNT_NEEDS_ACTION",
+        UserAuthorizationPending = "USER_AUTHORIZATION_PENDING",
+        UserAuthorizationNotGranted = "USER_AUTHORIZATION_NOT_GRANTED",
+        UserInputTimeout = "USER_INPUT_TIMEOUT",
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl ExecutionErrorCodes {
+    /// Whether the error can only be solved by the user, rather than by
+    /// retrying the update.
+    pub fn requires_user_action(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidCredentials
+                | Self::InvalidCredentialsMfa
+                | Self::AlreadyLoggedIn
+                | Self::AccountLocked
+                | Self::AccountCredentialsReset
+                | Self::AccountNeedsAction
+                | Self::UserAuthorizationPending
+                | Self::UserAuthorizationNotGranted
+                | Self::UserInputTimeout
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecutionErrorResult {
     pub code: ExecutionErrorCodes,
@@ -29,26 +49,211 @@ pub struct ExecutionErrorResult {
     pub attributes: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ExecutionStatus {
-    LoginInProgress,
-    WaitingUserInput,
-    WaitingUserAction,
-    LoginMfaInProgress,
-    AccountsInProgress,
-    TransactionsInProgress,
-    PaymentDataInProgress,
-    CreditcardsInProgress,
-    InvestmentsInProgress,
-    InvestmentsTransactionsInProgress,
-    OpportunitiesInProgress,
-    IdentityInProgress,
-    MergeError,
-    Error,
-    Success,
-    PartialSuccess,
-    Creating,
-    CreateError,
-    Created,
+api_enum! {
+    pub enum ExecutionStatus {
+        LoginInProgress = "LOGIN_IN_PROGRESS",
+        WaitingUserInput = "WAITING_USER_INPUT",
+        WaitingUserAction = "WAITING_USER_ACTION",
+        LoginMfaInProgress = "LOGIN_MFA_IN_PROGRESS",
+        AccountsInProgress = "ACCOUNTS_IN_PROGRESS",
+        TransactionsInProgress = "TRANSACTIONS_IN_PROGRESS",
+        PaymentDataInProgress = "PAYMENT_DATA_IN_PROGRESS",
+        CreditcardsInProgress = "CREDITCARDS_IN_PROGRESS",
+        InvestmentsInProgress = "INVESTMENTS_IN_PROGRESS",
+        InvestmentsTransactionsInProgress = "INVESTMENTS_TRANSACTIONS_IN_PROGRESS",
+        OpportunitiesInProgress = "OPPORTUNITIES_IN_PROGRESS",
+        IdentityInProgress = "IDENTITY_IN_PROGRESS",
+        MergeError = "MERGE_ERROR",
+        Error = "ERROR",
+        Success = "SUCCESS",
+        PartialSuccess = "PARTIAL_SUCCESS",
+        Creating = "CREATING",
+        CreateError = "CREATE_ERROR",
+        Created = "CREATED",
+    }
+}
+
+impl ExecutionStatus {
+    /// Whether the execution has ended. Executions waiting for the user
+    /// haven't: they resume once the user answers. Unknown statuses are not
+    /// terminal, so callers keep polling rather than stop early.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Success
+                | Self::PartialSuccess
+                | Self::Error
+                | Self::MergeError
+                | Self::CreateError
+        )
+    }
+
+    /// Whether the execution ended with an error. `PartialSuccess` is not an
+    /// error: some products were synced, see the item's `status_detail`.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error | Self::MergeError | Self::CreateError)
+    }
+
+    pub fn is_waiting_for_user(&self) -> bool {
+        matches!(self, Self::WaitingUserInput | Self::WaitingUserAction)
+    }
+
+    /// Product being synced, for the `*_IN_PROGRESS` statuses after login.
+    pub fn progress_stage(&self) -> Option<ProductType> {
+        match self {
+            Self::AccountsInProgress => Some(ProductType::Accounts),
+            Self::TransactionsInProgress => Some(ProductType::Transactions),
+            Self::PaymentDataInProgress => Some(ProductType::PaymentData),
+            Self::CreditcardsInProgress => Some(ProductType::CreditCards),
+            Self::InvestmentsInProgress => Some(ProductType::Investments),
+            Self::InvestmentsTransactionsInProgress => Some(ProductType::InvestmentsTransactions),
+            Self::OpportunitiesInProgress => Some(ProductType::Opportunities),
+            Self::IdentityInProgress => Some(ProductType::Identity),
+            _ => None,
+        }
+    }
+
+    /// Whether an execution can move from this status straight to `next`.
+    ///
+    /// An item is created (`CREATING`, `CREATED`), logs in, possibly waiting
+    /// for the user to send an MFA token or act on the institution's site,
+    /// syncs its products in any order, and ends, possibly right after the
+    /// user sends the MFA token. Updating an item restarts it from `CREATED`
+    /// or `LOGIN_IN_PROGRESS`. Polling can skip statuses, so two consecutive
+    /// polls don't always show a direct transition.
+    /// Staying in the same status and moving from or to an unknown status
+    /// are always allowed.
+    pub fn can_transition_to(&self, next: &ExecutionStatus) -> bool {
+        if self == next || self.is_unknown() || next.is_unknown() {
+            return true;
+        }
+
+        let is_restart = matches!(next, Self::Created | Self::LoginInProgress);
+        let is_sync = next.progress_stage().is_some();
+        let is_end = matches!(
+            next,
+            Self::Success | Self::PartialSuccess | Self::Error | Self::MergeError
+        );
+
+        match self {
+            Self::Creating => matches!(next, Self::Created | Self::CreateError),
+            Self::Created => matches!(next, Self::LoginInProgress | Self::Error),
+            Self::LoginInProgress | Self::LoginMfaInProgress => {
+                next.is_waiting_for_user() || *next == Self::LoginMfaInProgress || is_sync || is_end
+            }
+            Self::WaitingUserInput | Self::WaitingUserAction => {
+                next.is_waiting_for_user()
+                    || *next == Self::LoginMfaInProgress
+                    || is_restart
+                    || is_sync
+                    || matches!(next, Self::Success | Self::PartialSuccess | Self::Error)
+            }
+            Self::AccountsInProgress
+            | Self::TransactionsInProgress
+            | Self::PaymentDataInProgress
+            | Self::CreditcardsInProgress
+            | Self::InvestmentsInProgress
+            | Self::InvestmentsTransactionsInProgress
+            | Self::OpportunitiesInProgress
+            | Self::IdentityInProgress => is_sync || is_end,
+            Self::Success | Self::PartialSuccess | Self::Error | Self::MergeError => is_restart,
+            Self::CreateError => false,
+            Self::Unknown(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "hyper")]
+    use crate::sandbox::SandboxScenario;
+    #[cfg(feature = "hyper")]
+    use crate::testing::default_item_script;
+
+    #[test]
+    fn can_classify_statuses() {
+        let terminal: Vec<_> = ExecutionStatus::KNOWN
+            .iter()
+            .filter(|status| status.is_terminal())
+            .collect();
+        assert_eq!(terminal.len(), 5);
+        assert!(terminal
+            .iter()
+            .all(|status| status.progress_stage().is_none() && !status.is_waiting_for_user()));
+        assert!(!ExecutionStatus::PartialSuccess.is_error());
+        assert!(ExecutionStatus::MergeError.is_error());
+
+        let stages = ExecutionStatus::KNOWN
+            .iter()
+            .filter_map(ExecutionStatus::progress_stage)
+            .count();
+        assert_eq!(stages, 8);
+        assert_eq!(
+            ExecutionStatus::CreditcardsInProgress.progress_stage(),
+            Some(ProductType::CreditCards)
+        );
+        assert_eq!(ExecutionStatus::LoginMfaInProgress.progress_stage(), None);
+
+        let unknown = ExecutionStatus::from("ACCOUNT_CHECKS_IN_PROGRESS".to_string());
+        assert!(!unknown.is_terminal() && !unknown.is_error() && !unknown.is_waiting_for_user());
+    }
+
+    #[cfg(feature = "hyper")]
+    #[test]
+    fn can_validate_transitions() {
+        let scripts = SandboxScenario::ALL
+            .iter()
+            .map(SandboxScenario::item_script)
+            .chain([default_item_script()]);
+        for script in scripts {
+            let statuses: Vec<_> = std::iter::once(ExecutionStatus::Created)
+                .chain(script.into_iter().map(|step| step.execution_status))
+                .collect();
+            for pair in statuses.windows(2) {
+                assert!(
+                    pair[0].can_transition_to(&pair[1]),
+                    "can't go from {} to {}",
+                    pair[0],
+                    pair[1]
+                );
+            }
+        }
+
+        use ExecutionStatus::*;
+        assert!(Creating.can_transition_to(&CreateError));
+        assert!(TransactionsInProgress.can_transition_to(&AccountsInProgress));
+        assert!(Success.can_transition_to(&LoginInProgress));
+        assert!(WaitingUserInput.can_transition_to(&Success));
+        assert!(WaitingUserInput.can_transition_to(&PartialSuccess));
+        assert!(WaitingUserAction.can_transition_to(&Success));
+        assert!(WaitingUserAction.can_transition_to(&PartialSuccess));
+        assert!(!WaitingUserInput.can_transition_to(&MergeError));
+        assert!(!Success.can_transition_to(&Error));
+        assert!(!AccountsInProgress.can_transition_to(&LoginInProgress));
+        assert!(!AccountsInProgress.can_transition_to(&WaitingUserInput));
+        assert!(!CreateError.can_transition_to(&Created));
+        assert!(Error.can_transition_to(&Unknown("QUEUED".to_string())));
+    }
+
+    #[test]
+    fn can_reach_an_end_from_every_status() {
+        for status in ExecutionStatus::KNOWN {
+            let mut reached = vec![status.clone()];
+            let mut index = 0;
+            while index < reached.len() && !reached[index].is_terminal() {
+                for next in ExecutionStatus::KNOWN {
+                    if reached[index].can_transition_to(next) && !reached.contains(next) {
+                        reached.push(next.clone());
+                    }
+                }
+                index += 1;
+            }
+            assert!(
+                reached.iter().any(ExecutionStatus::is_terminal),
+                "{} never ends",
+                status
+            );
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Conversions and formatting shared by the id newtypes, all delegating to
+/// the wrapped value.
+macro_rules! id_type {
+    ($name:ident($inner:ty)) => {
+        impl $name {
+            pub fn new(id: $inner) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(id: $inner) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = <$inner as FromStr>::Err;
+
+            fn from_str(id: &str) -> Result<Self, Self::Err> {
+                id.parse().map(Self)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+/// Ids of different resources don't mix, so a webhook id can't be used to
+/// fetch an item:
+///
+/// ```compile_fail
+/// # async fn run(client: pluggy::Client, api_key: &str, webhook: pluggy::Webhook) {
+/// client.get_item(api_key, webhook.id).await;
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub Uuid);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConnectorId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WebhookId(pub Uuid);
+
+/// Category ids are eight digit codes such as `01010000`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CategoryId(pub String);
+
+id_type!(ItemId(Uuid));
+id_type!(ConnectorId(i32));
+id_type!(WebhookId(Uuid));
+id_type!(CategoryId(String));
+
+impl CategoryId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for CategoryId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
@@ -2,19 +2,22 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use super::{Connector, ConnectorCredential, ExecutionErrorResult, ExecutionStatus};
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ItemStatus {
-    Updated,
-    Updating,
-    WaitingUserInput,
-    LoginError,
-    Outdated,
+use super::{
+    Connector, ConnectorCredential, ConnectorId, ExecutionErrorResult, ExecutionStatus, ItemId,
+    ProductType, Timestamp,
+};
+
+api_enum! {
+    pub enum ItemStatus {
+        Updated = "UPDATED",
+        Updating = "UPDATING",
+        WaitingUserInput = "WAITING_USER_INPUT",
+        LoginError = "LOGIN_ERROR",
+        Outdated = "OUTDATED",
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemProductsStatusDetail {
     pub accounts: ItemProductState,
@@ -25,33 +28,60 @@ pub struct ItemProductsStatusDetail {
     pub payment_data: ItemProductState,
 }
 
-#[derive(Debug, Deserialize)]
+impl ItemProductsStatusDetail {
+    /// State of `product`, for the products the API reports a state for.
+    pub fn get(&self, product: &ProductType) -> Option<&ItemProductState> {
+        match product {
+            ProductType::Accounts => Some(&self.accounts),
+            ProductType::CreditCards => Some(&self.credit_cards),
+            ProductType::Transactions => Some(&self.transactions),
+            ProductType::Investments => Some(&self.investments),
+            ProductType::Identity => Some(&self.identity),
+            ProductType::PaymentData => Some(&self.payment_data),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ProductType, &ItemProductState)> {
+        [
+            (ProductType::Accounts, &self.accounts),
+            (ProductType::CreditCards, &self.credit_cards),
+            (ProductType::Transactions, &self.transactions),
+            (ProductType::Investments, &self.investments),
+            (ProductType::Identity, &self.identity),
+            (ProductType::PaymentData, &self.payment_data),
+        ]
+        .into_iter()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemProductState {
     pub is_updated: bool,
-    pub last_updated_at: Option<String>, // date
+    pub last_updated_at: Option<Timestamp>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserAction {
     pub instructions: String,
     pub attributes: Option<HashMap<String, String>>,
-    pub expires_at: Option<String>, // date
+    pub expires_at: Option<Timestamp>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Item {
-    pub id: String,
+    pub id: ItemId,
     pub connector: Connector,
     pub status: ItemStatus,
     pub status_detail: Option<ItemProductsStatusDetail>,
     pub error: Option<ExecutionErrorResult>,
     pub execution_status: ExecutionStatus,
-    pub created_at: String,              // date
-    pub updated_at: String,              // date
-    pub last_updated_at: Option<String>, // date
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub last_updated_at: Option<Timestamp>,
     pub parameter: Option<ConnectorCredential>,
     pub webhook_url: Option<String>,
     pub client_user_id: Option<String>,
@@ -59,17 +89,218 @@ pub struct Item {
     pub consecutive_failed_login_attempts: i32,
 }
 
+impl Item {
+    /// Whether `product` was collected in the last update. Without a status
+    /// detail, every product of the connector is ready once the item is
+    /// updated.
+    pub fn is_product_ready(&self, product: &ProductType) -> bool {
+        match self
+            .status_detail
+            .as_ref()
+            .and_then(|detail| detail.get(product))
+        {
+            Some(state) => state.is_updated,
+            None => self.status == ItemStatus::Updated && self.connector.products.contains(product),
+        }
+    }
+
+    /// Whether the user has to act before the item can update again: fix
+    /// their credentials, send an MFA token or authorize access at their
+    /// institution. Errors on Pluggy's or the institution's side don't need
+    /// the user and are retried on the next update.
+    pub fn needs_user_action(&self) -> bool {
+        matches!(
+            self.status,
+            ItemStatus::LoginError | ItemStatus::WaitingUserInput
+        ) || matches!(
+            self.execution_status,
+            ExecutionStatus::WaitingUserInput | ExecutionStatus::WaitingUserAction
+        ) || self
+            .error
+            .as_ref()
+            .is_some_and(|error| error.code.requires_user_action())
+            || (self.consecutive_failed_login_attempts > 0 && self.status != ItemStatus::Updated)
+    }
+
+    /// Products whose last update is older than `max_age` at `now`, or that
+    /// were never updated. Without a status detail, the connector products
+    /// are stale or fresh together, following `Item::last_updated_at`.
+    #[cfg(feature = "chrono")]
+    pub fn stale_products_at(
+        &self,
+        now: Timestamp,
+        max_age: std::time::Duration,
+    ) -> Vec<ProductType> {
+        let is_stale = |updated_at: Option<&Timestamp>| {
+            updated_at.is_none_or(|updated_at| age(now, *updated_at) > max_age)
+        };
+
+        match &self.status_detail {
+            Some(detail) => detail
+                .iter()
+                .filter(|(product, _)| self.connector.products.contains(product))
+                .filter(|(_, state)| is_stale(state.last_updated_at.as_ref()))
+                .map(|(product, _)| product)
+                .collect(),
+            None if is_stale(self.last_updated_at.as_ref()) => self.connector.products.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn stale_products(&self, max_age: std::time::Duration) -> Vec<ProductType> {
+        self.stale_products_at(chrono::Utc::now(), max_age)
+    }
+
+    /// Age of the oldest product update at `now`, so the item is as fresh as
+    /// its stalest product. `None` if a product was never updated.
+    #[cfg(feature = "chrono")]
+    pub fn freshness_at(&self, now: Timestamp) -> Option<std::time::Duration> {
+        match &self.status_detail {
+            Some(detail) => detail
+                .iter()
+                .filter(|(product, _)| self.connector.products.contains(product))
+                .map(|(_, state)| state.last_updated_at.map(|updated_at| age(now, updated_at)))
+                .try_fold(std::time::Duration::ZERO, |oldest, age| {
+                    Some(oldest.max(age?))
+                }),
+            None => self.last_updated_at.map(|updated_at| age(now, updated_at)),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn freshness(&self) -> Option<std::time::Duration> {
+        self.freshness_at(chrono::Utc::now())
+    }
+}
+
+/// Time elapsed from `then` to `now`, zero if `then` is in the future.
+#[cfg(feature = "chrono")]
+fn age(now: Timestamp, then: Timestamp) -> std::time::Duration {
+    (now - then).to_std().unwrap_or_default()
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateItemRequest<'a> {
-    // TODO support additional fields
-    // pub client_user_id: Option<String>,
-    // pub webhook_url: Option<String>,
-    pub connector_id: i32,
+    pub connector_id: ConnectorId,
     pub parameters: &'a HashMap<String, String>,
+    pub client_user_id: Option<&'a str>,
+    pub webhook_url: Option<&'a str>,
+}
+
+/// Optional settings of `Client::create_item_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateItemOptions<'a> {
+    pub client_user_id: Option<&'a str>,
+    pub webhook_url: Option<&'a str>,
+    /// Sent as the `Idempotency-Key` header, which also lets a
+    /// `RetryPolicy` retry the request.
+    pub idempotency_key: Option<&'a str>,
 }
 
 #[derive(Serialize)]
 pub struct UpdateItemRequest<'a> {
     pub parameters: &'a HashMap<String, String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExecutionErrorCodes;
+
+    fn item() -> Item {
+        serde_json::from_str(include_str!("fixtures/item.json")).unwrap()
+    }
+
+    fn updated_item() -> Item {
+        Item {
+            status: ItemStatus::Updated,
+            status_detail: None,
+            error: None,
+            execution_status: ExecutionStatus::Success,
+            consecutive_failed_login_attempts: 0,
+            ..item()
+        }
+    }
+
+    #[test]
+    fn can_check_product_readiness() {
+        let item = item();
+        assert!(item.is_product_ready(&ProductType::Accounts));
+        assert!(!item.is_product_ready(&ProductType::CreditCards));
+        assert!(!item.is_product_ready(&ProductType::Opportunities));
+
+        let item = updated_item();
+        assert!(item.is_product_ready(&ProductType::CreditCards));
+        assert!(!item.is_product_ready(&ProductType::Investments));
+    }
+
+    #[test]
+    fn can_tell_when_user_action_is_needed() {
+        assert!(item().needs_user_action());
+        assert!(!updated_item().needs_user_action());
+
+        let error = |code| {
+            Some(ExecutionErrorResult {
+                code,
+                message: String::new(),
+                provider_message: None,
+                attributes: None,
+            })
+        };
+        let outdated = Item {
+            status: ItemStatus::Outdated,
+            execution_status: ExecutionStatus::Error,
+            error: error(ExecutionErrorCodes::SiteNotAvailable),
+            ..updated_item()
+        };
+        assert!(!outdated.needs_user_action());
+        assert!(Item {
+            error: error(ExecutionErrorCodes::AccountCredentialsReset),
+            ..outdated.clone()
+        }
+        .needs_user_action());
+        assert!(Item {
+            consecutive_failed_login_attempts: 2,
+            ..outdated
+        }
+        .needs_user_action());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn can_find_stale_products() {
+        use std::time::Duration;
+
+        let now: Timestamp = "2023-05-02T13:00:00Z".parse().unwrap();
+        let mut item = item();
+
+        assert_eq!(
+            item.stale_products_at(now, Duration::from_secs(2 * 60 * 60)),
+            vec![ProductType::CreditCards]
+        );
+        assert_eq!(
+            item.stale_products_at(now, Duration::from_secs(30 * 60))
+                .len(),
+            4
+        );
+        assert_eq!(item.freshness_at(now), None);
+
+        let detail = item.status_detail.as_mut().unwrap();
+        detail.credit_cards.last_updated_at = Some("2023-05-01T12:00:00Z".parse().unwrap());
+        assert_eq!(
+            item.freshness_at(now),
+            Some(Duration::from_secs(25 * 60 * 60))
+        );
+
+        let item = updated_item();
+        assert_eq!(
+            item.freshness_at(now),
+            Some(Duration::from_millis(98_958_713))
+        );
+        assert!(item
+            .stale_products_at(now, Duration::from_secs(24 * 60 * 60))
+            .contains(&ProductType::Transactions));
+    }
+}
@@ -0,0 +1,106 @@
+/// Declares an enum of the string values the API sends. Its `Unknown`
+/// variant keeps values added to the API after this crate was released, so
+/// responses carrying them still deserialize, and serialize back unchanged.
+macro_rules! api_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value this version of the crate doesn't know about.
+            Unknown(String),
+        }
+
+        impl $name {
+            /// Every value this version of the crate knows about.
+            pub const KNOWN: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// The value sent by the API.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(value) => value,
+                }
+            }
+
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, Self::Unknown(_))
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value),
+                }
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from(value.to_string()))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <String as serde::Deserialize>::deserialize(deserializer).map(Self::from)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{Country, ExecutionStatus, WebhookEvent};
+
+    #[test]
+    fn can_deserialize_known_values() {
+        let status: ExecutionStatus = serde_json::from_value(json!("LOGIN_IN_PROGRESS")).unwrap();
+        assert_eq!(status, ExecutionStatus::LoginInProgress);
+
+        let event: WebhookEvent = serde_json::from_value(json!("item/created")).unwrap();
+        assert_eq!(event, WebhookEvent::ItemCreated);
+        assert_eq!(serde_json::to_value(&event).unwrap(), json!("item/created"));
+
+        assert_eq!("MX".parse::<Country>().unwrap(), Country::MX);
+    }
+
+    #[test]
+    fn can_round_trip_unknown_values() {
+        let status: ExecutionStatus =
+            serde_json::from_value(json!("BIOMETRICS_IN_PROGRESS")).unwrap();
+        assert_eq!(
+            status,
+            ExecutionStatus::Unknown("BIOMETRICS_IN_PROGRESS".to_string())
+        );
+        assert!(status.is_unknown());
+        assert_eq!(status.to_string(), "BIOMETRICS_IN_PROGRESS");
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            json!("BIOMETRICS_IN_PROGRESS")
+        );
+    }
+}
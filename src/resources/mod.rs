@@ -1,20 +1,51 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+pub use crate::resources::boleto::*;
 pub use crate::resources::category::*;
 pub use crate::resources::connector::*;
+pub use crate::resources::consent::*;
 pub use crate::resources::execution::*;
+pub use crate::resources::id::*;
 pub use crate::resources::item::*;
+pub use crate::resources::money::*;
+pub use crate::resources::payment::*;
+pub use crate::resources::smart_transfer::*;
+pub use crate::resources::transaction::*;
 pub use crate::resources::validation::*;
 pub use crate::resources::webhook::*;
 
+#[macro_use]
+mod macros;
+
+mod boleto;
 mod category;
 mod connector;
+mod consent;
 mod execution;
+mod id;
 mod item;
+mod money;
+mod payment;
+mod smart_transfer;
+mod transaction;
 mod validation;
 mod webhook;
 
-#[derive(Debug, Deserialize)]
+/// Date and time returned by the API: `chrono::DateTime<Utc>` with the
+/// `chrono` feature, the ISO 8601 string otherwise.
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = String;
+
+/// Calendar date, such as a boleto due date: `chrono::NaiveDate` with the
+/// `chrono` feature, `YYYY-MM-DD` otherwise.
+#[cfg(feature = "chrono")]
+pub type Date = chrono::NaiveDate;
+#[cfg(not(feature = "chrono"))]
+pub type Date = String;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PageResponse<T> {
     pub results: Vec<T>,
@@ -22,3 +53,78 @@ pub struct PageResponse<T> {
     pub total_pages: i32,
     pub total: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Deserializes a captured API response and serializes it back, checking
+    /// no field is lost or renamed on the way.
+    fn assert_round_trips<T>(fixture: &str)
+    where
+        T: DeserializeOwned + Serialize + PartialEq + Debug,
+    {
+        let json: Value = serde_json::from_str(fixture).unwrap();
+        let model: T = serde_json::from_value(json.clone()).unwrap();
+        let serialized = serde_json::to_value(&model).unwrap();
+
+        assert_same_json(&json, &serialized, "$");
+        assert_eq!(serde_json::from_value::<T>(serialized).unwrap(), model);
+    }
+
+    fn assert_same_json(expected: &Value, actual: &Value, path: &str) {
+        match (expected, actual) {
+            (Value::Object(expected), Value::Object(actual)) => {
+                let mut expected_keys: Vec<_> = expected.keys().collect();
+                let mut actual_keys: Vec<_> = actual.keys().collect();
+                expected_keys.sort();
+                actual_keys.sort();
+                assert_eq!(expected_keys, actual_keys, "fields of {}", path);
+
+                for (key, value) in expected {
+                    assert_same_json(value, &actual[key], &format!("{}.{}", path, key));
+                }
+            }
+            (Value::Array(expected), Value::Array(actual)) => {
+                assert_eq!(expected.len(), actual.len(), "length of {}", path);
+                for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                    assert_same_json(expected, actual, &format!("{}[{}]", path, index));
+                }
+            }
+            (Value::Number(expected), Value::Number(actual)) => {
+                assert_eq!(expected.as_f64(), actual.as_f64(), "{}", path)
+            }
+            // Timestamps may come back in another RFC 3339 form, such as
+            // without milliseconds.
+            (Value::String(expected), Value::String(actual)) => assert!(
+                expected == actual
+                    || expected
+                        .parse::<Timestamp>()
+                        .is_ok_and(|timestamp| actual.parse() == Ok(timestamp)),
+                "{}: {} != {}",
+                path,
+                expected,
+                actual
+            ),
+            (expected, actual) => assert_eq!(expected, actual, "{}", path),
+        }
+    }
+
+    #[test]
+    fn can_round_trip_fixtures() {
+        assert_round_trips::<Item>(include_str!("fixtures/item.json"));
+        assert_round_trips::<PageResponse<Transaction>>(include_str!("fixtures/transactions.json"));
+        assert_round_trips::<Webhook>(include_str!("fixtures/webhook.json"));
+        assert_round_trips::<ValidationResult>(include_str!("fixtures/validation.json"));
+        assert_round_trips::<Boleto>(include_str!("fixtures/boleto.json"));
+        assert_round_trips::<SmartTransferPreauthorization>(include_str!(
+            "fixtures/smart_transfer_preauthorization.json"
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Mul, Neg};
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+api_enum! {
+    /// ISO 4217 currency code.
+    pub enum Currency {
+        BRL = "BRL",
+        USD = "USD",
+        EUR = "EUR",
+        ARS = "ARS",
+        MXN = "MXN",
+    }
+}
+
+/// An amount of money in a currency.
+///
+/// Amounts in different currencies are never combined: adding, subtracting
+/// or comparing them fails instead of mixing, say, reais and dollars.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    amount: Decimal,
+    currency: Currency,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyMismatch {
+    pub expected: Currency,
+    pub found: Currency,
+}
+
+impl fmt::Display for CurrencyMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected an amount in {}, found one in {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for CurrencyMismatch {}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub fn brl(amount: Decimal) -> Self {
+        Self::new(amount, Currency::BRL)
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    /// The shortest decimal that reads back as `amount`, so `0.1` is `0.1`
+    /// rather than its exact binary value. `None` for NaN and infinities.
+    pub fn from_f64(amount: f64, currency: Currency) -> Option<Self> {
+        decimal_from_f64(amount).map(|amount| Self::new(amount, currency))
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        decimal_to_f64(&self.amount)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(self.amount.abs(), self.currency.clone())
+    }
+
+    /// Rounds to `decimal_places` with banker's rounding.
+    pub fn round_dp(&self, decimal_places: u32) -> Self {
+        Self::new(self.amount.round_dp(decimal_places), self.currency.clone())
+    }
+
+    pub fn ensure_currency(&self, currency: &Currency) -> Result<(), CurrencyMismatch> {
+        if self.currency == *currency {
+            Ok(())
+        } else {
+            Err(CurrencyMismatch {
+                expected: currency.clone(),
+                found: self.currency.clone(),
+            })
+        }
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, CurrencyMismatch> {
+        other.ensure_currency(&self.currency)?;
+        Ok(Self::new(self.amount + other.amount, self.currency.clone()))
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, CurrencyMismatch> {
+        other.ensure_currency(&self.currency)?;
+        Ok(Self::new(self.amount - other.amount, self.currency.clone()))
+    }
+
+    /// Adds up `amounts`, which must all be in `currency`.
+    pub fn sum<'a>(
+        currency: Currency,
+        amounts: impl IntoIterator<Item = &'a Money>,
+    ) -> Result<Money, CurrencyMismatch> {
+        amounts
+            .into_iter()
+            .try_fold(Self::zero(currency), |total, amount| {
+                total.checked_add(amount)
+            })
+    }
+}
+
+impl PartialOrd for Money {
+    /// `None` for amounts in different currencies.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency == other.currency {
+            self.amount.partial_cmp(&other.amount)
+        } else {
+            None
+        }
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Self::new(-self.amount, self.currency)
+    }
+}
+
+impl Mul<Decimal> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: Decimal) -> Money {
+        Self::new(self.amount * factor, self.currency)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.currency, self.amount)
+    }
+}
+
+impl From<Money> for Decimal {
+    fn from(money: Money) -> Decimal {
+        money.amount
+    }
+}
+
+fn decimal_from_f64(amount: f64) -> Option<Decimal> {
+    if !amount.is_finite() {
+        return None;
+    }
+
+    let amount = amount.to_string();
+    Decimal::from_str(&amount)
+        .or_else(|_| Decimal::from_scientific(&amount))
+        .ok()
+}
+
+fn decimal_to_f64(amount: &Decimal) -> f64 {
+    amount.to_string().parse().unwrap_or_default()
+}
+
+/// Amounts the API sends as JSON numbers, written back digit for digit
+/// rather than through `f64`.
+pub(crate) mod number {
+    pub use rust_decimal::serde::arbitrary_precision::{deserialize, serialize};
+
+    pub mod option {
+        pub use rust_decimal::serde::arbitrary_precision_option::{deserialize, serialize};
+    }
+}
+
+/// Amounts in reais the API sends as JSON numbers, such as payment and
+/// boleto amounts.
+pub(crate) mod brl {
+    use serde::{Deserializer, Serializer};
+
+    use super::{number, Money};
+
+    pub fn serialize<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+        number::serialize(&money.amount, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        number::deserialize(deserializer).map(Money::brl)
+    }
+
+    pub mod option {
+        use serde::{Deserializer, Serializer};
+
+        use super::super::{number, Money};
+
+        pub fn serialize<S: Serializer>(
+            money: &Option<Money>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            number::option::serialize(&money.as_ref().map(Money::amount), serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Money>, D::Error> {
+            number::option::deserialize(deserializer).map(|amount| amount.map(Money::brl))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brl(amount: &str) -> Money {
+        Money::brl(amount.parse().unwrap())
+    }
+
+    #[test]
+    fn can_add_amounts_in_the_same_currency() {
+        assert_eq!(brl("0.1").checked_add(&brl("0.2")), Ok(brl("0.3")));
+        assert_eq!(brl("10").checked_sub(&brl("12.5")), Ok(brl("-2.5")));
+        assert_eq!(
+            Money::sum(Currency::BRL, &[brl("1.10"), brl("2.20"), brl("3.30")]),
+            Ok(brl("6.60"))
+        );
+        assert!(brl("1") < brl("1.01"));
+    }
+
+    #[test]
+    fn can_refuse_to_mix_currencies() {
+        let dollars = Money::new("1".parse().unwrap(), Currency::USD);
+        let mismatch = CurrencyMismatch {
+            expected: Currency::BRL,
+            found: Currency::USD,
+        };
+
+        assert_eq!(brl("1").checked_add(&dollars), Err(mismatch.clone()));
+        assert_eq!(brl("1").checked_sub(&dollars), Err(mismatch.clone()));
+        assert_eq!(
+            Money::sum(Currency::BRL, &[brl("1"), dollars.clone()]),
+            Err(mismatch)
+        );
+        assert_eq!(brl("1").partial_cmp(&dollars), None);
+    }
+
+    #[test]
+    fn can_convert_floats_without_binary_noise() {
+        let money = Money::from_f64(1500.1, Currency::BRL).unwrap();
+        assert_eq!(money.amount().to_string(), "1500.1");
+        assert_eq!(money.to_f64(), 1500.1);
+        assert_eq!(Money::from_f64(f64::NAN, Currency::BRL), None);
+        assert_eq!(brl("-12.99").to_string(), "BRL -12.99");
+    }
+
+    #[test]
+    fn can_round_trip_amounts_exactly() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Payment {
+            #[serde(with = "brl")]
+            amount: Money,
+        }
+
+        let sum = brl("0.1").checked_add(&brl("0.2")).unwrap();
+        let json = serde_json::to_string(&Payment { amount: sum }).unwrap();
+        assert_eq!(json, r#"{"amount":0.3}"#);
+
+        let json = r#"{"amount":9007199254740993.01}"#;
+        let payment: Payment = serde_json::from_str(json).unwrap();
+        assert_eq!(payment.amount, brl("9007199254740993.01"));
+        assert_eq!(serde_json::to_string(&payment).unwrap(), json);
+    }
+}
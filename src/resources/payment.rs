@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Money, Timestamp};
+
+api_enum! {
+    pub enum PaymentAccountType {
+        CheckingAccount = "CHECKING_ACCOUNT",
+        SavingsAccount = "SAVINGS_ACCOUNT",
+        GuaranteedAccount = "GUARANTEED_ACCOUNT",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentInstitution {
+    pub id: String,
+    pub name: String,
+    pub trade_name: Option<String>,
+    pub ispb: Option<String>,
+    pub compe: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRecipientAccount {
+    pub branch: String,
+    pub number: String,
+    #[serde(rename = "type")]
+    pub account_type: PaymentAccountType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRecipient {
+    pub id: String,
+    pub tax_number: String,
+    pub name: String,
+    pub payment_institution: Option<PaymentInstitution>,
+    pub is_default: bool,
+    pub account: Option<PaymentRecipientAccount>,
+    pub pix_key: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+api_enum! {
+    pub enum PaymentRequestStatus {
+        Created = "CREATED",
+        InProgress = "IN_PROGRESS",
+        WaitingPayerAuthorization = "WAITING_PAYER_AUTHORIZATION",
+        Completed = "COMPLETED",
+        Canceled = "CANCELED",
+        Error = "ERROR",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequestBoleto {
+    pub digitable_line: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    pub id: String,
+    #[serde(with = "super::money::brl")]
+    pub amount: Money,
+    pub description: Option<String>,
+    pub status: PaymentRequestStatus,
+    pub payment_url: Option<String>,
+    pub recipient_id: Option<String>,
+    pub boleto: Option<PaymentRequestBoleto>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoletoPaymentRequestBoleto<'a> {
+    pub digitable_line: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBoletoPaymentRequest<'a> {
+    pub boleto: BoletoPaymentRequestBoleto<'a>,
+    pub description: Option<&'a str>,
+}
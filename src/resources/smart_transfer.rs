@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Connector, ConnectorId, Money, PaymentRecipient, Timestamp};
+
+api_enum! {
+    pub enum SmartTransferPreauthorizationStatus {
+        Created = "CREATED",
+        Completed = "COMPLETED",
+        Revoked = "REVOKED",
+        Rejected = "REJECTED",
+        Error = "ERROR",
+    }
+}
+
+api_enum! {
+    pub enum SmartTransferPaymentStatus {
+        Created = "CREATED",
+        InProgress = "IN_PROGRESS",
+        Completed = "COMPLETED",
+        Error = "ERROR",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartTransferCallbackUrls {
+    pub success: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartTransferPreauthorization {
+    pub id: String,
+    pub status: SmartTransferPreauthorizationStatus,
+    pub consent_url: Option<String>,
+    pub connector: Option<Connector>,
+    pub recipients: Vec<PaymentRecipient>,
+    pub callback_urls: Option<SmartTransferCallbackUrls>,
+    pub client_preauthorization_id: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+impl SmartTransferPreauthorization {
+    /// Whether the user still has to grant consent through `consent_url`.
+    pub fn is_consent_pending(&self) -> bool {
+        matches!(self.status, SmartTransferPreauthorizationStatus::Created)
+    }
+
+    /// Whether consent was granted and payments can be created against it.
+    pub fn is_consent_granted(&self) -> bool {
+        matches!(self.status, SmartTransferPreauthorizationStatus::Completed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartTransferPayment {
+    pub id: String,
+    pub preauthorization_id: String,
+    pub recipient_id: String,
+    pub status: SmartTransferPaymentStatus,
+    #[serde(with = "super::money::brl")]
+    pub amount: Money,
+    pub description: Option<String>,
+    pub client_payment_id: Option<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSmartTransferPreauthorizationRequest<'a> {
+    pub connector_id: ConnectorId,
+    pub parameters: &'a HashMap<String, String>,
+    pub recipient_ids: &'a [String],
+    pub callback_urls: Option<&'a SmartTransferCallbackUrls>,
+    pub client_preauthorization_id: Option<&'a str>,
+}
+
+/// Optional settings of `Client::create_smart_transfer_preauthorization_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateSmartTransferPreauthorizationOptions<'a> {
+    /// Where the user is sent after granting or refusing consent.
+    pub callback_urls: Option<&'a SmartTransferCallbackUrls>,
+    pub client_preauthorization_id: Option<&'a str>,
+}
+
+/// Optional settings of `Client::create_smart_transfer_payment_with_idempotency_key`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateSmartTransferPaymentOptions<'a> {
+    pub description: Option<&'a str>,
+    pub client_payment_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSmartTransferPaymentRequest<'a> {
+    pub preauthorization_id: &'a str,
+    pub recipient_id: &'a str,
+    #[serde(with = "super::money::brl")]
+    pub amount: &'a Money,
+    pub description: Option<&'a str>,
+    pub client_payment_id: Option<&'a str>,
+}
@@ -0,0 +1,119 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::{CategoryId, Currency, Money, Timestamp};
+
+api_enum! {
+    pub enum TransactionType {
+        Debit = "DEBIT",
+        Credit = "CREDIT",
+    }
+}
+
+api_enum! {
+    pub enum TransactionStatus {
+        Pending = "PENDING",
+        Posted = "POSTED",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "TransactionJson", into = "TransactionJson")]
+pub struct Transaction {
+    pub id: String,
+    pub account_id: String,
+    pub description: String,
+    pub description_raw: Option<String>,
+    /// In the currency of the transaction, sent as `currencyCode`.
+    pub amount: Money,
+    /// In the currency of the account, only sent when the transaction was in
+    /// another currency, as for purchases abroad on a card. Tagged with
+    /// `currencyCode`, the only currency the payload carries.
+    pub amount_in_account_currency: Option<Money>,
+    pub date: Timestamp,
+    /// Account balance after the transaction, tagged with `currencyCode`.
+    pub balance: Option<Money>,
+    pub category: Option<String>,
+    pub category_id: Option<CategoryId>,
+    pub provider_code: Option<String>,
+    pub status: Option<TransactionStatus>,
+    pub transaction_type: TransactionType,
+}
+
+/// `Transaction` as sent by the API, with its currency apart from its amount.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionJson {
+    id: String,
+    account_id: String,
+    description: String,
+    description_raw: Option<String>,
+    currency_code: Currency,
+    #[serde(with = "super::money::number")]
+    amount: Decimal,
+    #[serde(with = "super::money::number::option")]
+    amount_in_account_currency: Option<Decimal>,
+    date: Timestamp,
+    #[serde(with = "super::money::number::option")]
+    balance: Option<Decimal>,
+    category: Option<String>,
+    category_id: Option<CategoryId>,
+    provider_code: Option<String>,
+    status: Option<TransactionStatus>,
+    #[serde(rename = "type")]
+    transaction_type: TransactionType,
+}
+
+impl From<TransactionJson> for Transaction {
+    fn from(json: TransactionJson) -> Self {
+        let currency = json.currency_code;
+
+        Self {
+            id: json.id,
+            account_id: json.account_id,
+            description: json.description,
+            description_raw: json.description_raw,
+            amount: Money::new(json.amount, currency.clone()),
+            amount_in_account_currency: json
+                .amount_in_account_currency
+                .map(|amount| Money::new(amount, currency.clone())),
+            date: json.date,
+            balance: json.balance.map(|balance| Money::new(balance, currency)),
+            category: json.category,
+            category_id: json.category_id,
+            provider_code: json.provider_code,
+            status: json.status,
+            transaction_type: json.transaction_type,
+        }
+    }
+}
+
+impl From<Transaction> for TransactionJson {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            account_id: transaction.account_id,
+            description: transaction.description,
+            description_raw: transaction.description_raw,
+            currency_code: transaction.amount.currency().clone(),
+            amount: transaction.amount.amount(),
+            amount_in_account_currency: transaction
+                .amount_in_account_currency
+                .as_ref()
+                .map(Money::amount),
+            date: transaction.date,
+            balance: transaction.balance.as_ref().map(Money::amount),
+            category: transaction.category,
+            category_id: transaction.category_id,
+            provider_code: transaction.provider_code,
+            status: transaction.status,
+            transaction_type: transaction.transaction_type,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTransactionCategoryRequest<'a> {
+    pub category_id: &'a CategoryId,
+}
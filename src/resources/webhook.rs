@@ -1,15 +1,17 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+use super::{Timestamp, WebhookId};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Webhook {
-    pub id: String,
+    pub id: WebhookId,
     pub url: String,
     pub event: WebhookEvent,
-    pub created_at: String,          // date
-    pub updated_at: String,          // date
-    pub disabled_at: Option<String>, // date
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    pub disabled_at: Option<Timestamp>,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,24 +31,16 @@ pub struct UpdateWebhookRequest<'a> {
     pub enabled: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum WebhookEvent {
-    #[serde(rename = "item/created")]
-    ItemCreated,
-    #[serde(rename = "item/updated")]
-    ItemUpdated,
-    #[serde(rename = "item/error")]
-    ItemError,
-    #[serde(rename = "item/deleted")]
-    ItemDeleted,
-    #[serde(rename = "item/waiting_user_input")]
-    ItemWaitingUserInput,
-    #[serde(rename = "item/login_succeeded")]
-    ItemLoginSucceeded,
-    #[serde(rename = "connector/status_updated")]
-    ConnectorStatusUpdated,
-    #[serde(rename = "transactions/deleted")]
-    TransactionsDeleted,
-    #[serde(rename = "all")]
-    All,
+api_enum! {
+    pub enum WebhookEvent {
+        ItemCreated = "item/created",
+        ItemUpdated = "item/updated",
+        ItemError = "item/error",
+        ItemDeleted = "item/deleted",
+        ItemWaitingUserInput = "item/waiting_user_input",
+        ItemLoginSucceeded = "item/login_succeeded",
+        ConnectorStatusUpdated = "connector/status_updated",
+        TransactionsDeleted = "transactions/deleted",
+        All = "all",
+    }
 }
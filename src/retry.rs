@@ -0,0 +1,344 @@
+//! Retries of failed requests.
+//!
+//! `RetryPolicy` is a middleware: a `Client` built with
+//! `ClientBuilder::retry_policy` retries requests that
+//! fail with a transport error or a transient status (408, 429, 500, 502, 503
+//! and 504), waiting with exponential backoff and jitter between attempts, or
+//! for as long as the `Retry-After` header of a 429 or 503 response asks.
+//!
+//! Only idempotent methods are retried by default. POSTs are retried when
+//! they carry an `Idempotency-Key` header, which `Client` sets from options
+//! such as `CreateItemOptions::idempotency_key`.
+
+use std::time::{Duration, SystemTime};
+
+use http::{HeaderMap, Method, Request, StatusCode};
+
+use crate::middleware::{Middleware, Next};
+use crate::transport::{HttpRequest, HttpResponse, TransportFuture};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    /// Up to `max_attempts` attempts per request, counting the first one.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Sends every request once.
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Backoff before the first retry, doubled on every following one.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Waits a random duration between half and all of the backoff, so
+    /// clients failing together don't retry together.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Also retries POST and PATCH requests without an idempotency key, which
+    /// may apply them twice.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn is_retryable_request(&self, method: &Method, headers: &HeaderMap) -> bool {
+        self.retry_non_idempotent
+            || matches!(
+                *method,
+                Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+            )
+            || headers.contains_key(IDEMPOTENCY_KEY_HEADER)
+    }
+
+    pub fn is_retryable_status(&self, status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::REQUEST_TIMEOUT
+                | StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Backoff before retry number `retry`, starting at 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            backoff.mul_f64(0.5 + fastrand::f64() / 2.0)
+        } else {
+            backoff
+        }
+    }
+
+    /// How long to wait before retrying after `response`: its `Retry-After`
+    /// on 429 and 503, capped at the maximum backoff, the backoff otherwise.
+    pub fn delay(&self, retry: u32, response: Option<&HttpResponse>) -> Duration {
+        response
+            .filter(|response| {
+                matches!(
+                    response.status(),
+                    StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+                )
+            })
+            .and_then(retry_after)
+            .map(|delay| delay.min(self.max_backoff))
+            .unwrap_or_else(|| self.backoff(retry))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, backing off from 500ms up to 30s.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// Parses a `Retry-After` header, in seconds or as an HTTP date.
+pub fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+impl Middleware for RetryPolicy {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        Box::pin(async move {
+            if self.max_attempts <= 1
+                || !self.is_retryable_request(request.method(), request.headers())
+            {
+                return next.run(request).await;
+            }
+
+            let mut retry = 0;
+            loop {
+                retry += 1;
+                let result = next.run(clone_request(&request)).await;
+                if retry >= self.max_attempts {
+                    return result;
+                }
+
+                let delay = match &result {
+                    Ok(response) if self.is_retryable_status(response.status()) => {
+                        self.delay(retry, Some(response))
+                    }
+                    Ok(_) => return result,
+                    Err(_) => self.delay(retry, None),
+                };
+                #[cfg(feature = "tracing")]
+                {
+                    let span = tracing::Span::current();
+                    span.record("retries", retry);
+                    tracing::debug!(
+                        retry,
+                        delay_ms = delay.as_millis() as u64,
+                        "retrying request"
+                    );
+                }
+                tokio::time::sleep(delay).await;
+            }
+        })
+    }
+}
+
+fn clone_request(request: &HttpRequest) -> HttpRequest {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::{json_response, HttpTransport, MemoryTransport};
+
+    async fn send(
+        policy: &RetryPolicy,
+        transport: &dyn HttpTransport,
+        request: HttpRequest,
+    ) -> HttpResponse {
+        let middlewares: [Arc<dyn Middleware>; 1] = [Arc::new(policy.clone())];
+        Next::new(&middlewares, transport)
+            .run(request)
+            .await
+            .unwrap()
+    }
+
+    fn flaky_transport(failures: usize, status: StatusCode) -> Arc<MemoryTransport> {
+        let calls = AtomicUsize::new(0);
+        Arc::new(MemoryTransport::new(move |_| {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                let mut response = json_response(status, &json!({ "message": "try again" }));
+                response
+                    .headers_mut()
+                    .insert(http::header::RETRY_AFTER, "0".parse().unwrap());
+                response
+            } else {
+                json_response(StatusCode::OK, &json!({ "ok": true }))
+            }
+        }))
+    }
+
+    fn request(method: Method, idempotency_key: Option<&str>) -> HttpRequest {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri("https://api.pluggy.ai/items");
+        if let Some(key) = idempotency_key {
+            builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        builder.body(Vec::new()).unwrap()
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(3).initial_backoff(Duration::ZERO)
+    }
+
+    #[tokio::test]
+    async fn can_retry_idempotent_requests() {
+        let inner = flaky_transport(2, StatusCode::SERVICE_UNAVAILABLE);
+        let response = send(&policy(), &*inner, request(Method::GET, None)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(inner.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn can_give_up_after_max_attempts() {
+        let inner = flaky_transport(5, StatusCode::TOO_MANY_REQUESTS);
+        let response = send(&policy(), &*inner, request(Method::GET, None)).await;
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(inner.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn can_retry_posts_only_with_idempotency_key() {
+        let inner = flaky_transport(1, StatusCode::BAD_GATEWAY);
+        let response = send(&policy(), &*inner, request(Method::POST, None)).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(inner.requests().len(), 1);
+
+        let inner = flaky_transport(1, StatusCode::BAD_GATEWAY);
+        let response = send(
+            &policy(),
+            &*inner,
+            request(Method::POST, Some("create-item-1")),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(inner.requests().len(), 2);
+        assert_eq!(
+            inner.requests()[1].headers[IDEMPOTENCY_KEY_HEADER],
+            "create-item-1"
+        );
+    }
+
+    #[test]
+    fn can_back_off_exponentially() {
+        let policy = RetryPolicy::new(10)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1))
+            .jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+
+        let policy = policy.jitter(true);
+        for retry in 1..10 {
+            let backoff = policy.backoff(retry);
+            let max = policy.clone().jitter(false).backoff(retry);
+            assert!(backoff >= max / 2 && backoff <= max);
+        }
+    }
+
+    #[test]
+    fn can_honor_retry_after() {
+        let policy = RetryPolicy::default().jitter(false);
+        let mut response = json_response(StatusCode::TOO_MANY_REQUESTS, &json!({}));
+        response
+            .headers_mut()
+            .insert(http::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(policy.delay(1, Some(&response)), Duration::from_secs(7));
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(20));
+        response
+            .headers_mut()
+            .insert(http::header::RETRY_AFTER, date.parse().unwrap());
+        let delay = policy.delay(1, Some(&response));
+        assert!(delay > Duration::from_secs(15) && delay <= Duration::from_secs(20));
+
+        response
+            .headers_mut()
+            .insert(http::header::RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(policy.delay(1, Some(&response)), Duration::from_secs(30));
+        let policy = policy.max_backoff(Duration::from_secs(5));
+        assert_eq!(policy.delay(1, Some(&response)), Duration::from_secs(5));
+
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        assert_eq!(policy.delay(1, Some(&response)), policy.backoff(1));
+    }
+}
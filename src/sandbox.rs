@@ -0,0 +1,281 @@
+//! Scenarios of the Pluggy Bank sandbox connector, enabled with the
+//! `test-util` feature.
+//!
+//! Each `SandboxScenario` carries the magic credentials that make the sandbox
+//! connector end its execution in a given way, as documented in
+//! <https://docs.pluggy.ai/docs/sandbox>, so every error branch can be
+//! exercised on purpose:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use pluggy::sandbox::{assert_scenario, SandboxScenario};
+//! use pluggy::Client;
+//!
+//! let (client, api_key) = Client::new_from_env_with_api_key().await?;
+//! let item = assert_scenario(&client, &api_key, SandboxScenario::AccountLocked).await;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `testing::MockServer` follows the same scenarios for the sandbox connector.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::testing::ItemStep;
+use crate::{Client, ConnectorId, ExecutionErrorCodes, ExecutionStatus, Item, ItemStatus};
+
+/// Id of the "Pluggy Bank" sandbox connector.
+pub const PLUGGY_BANK_CONNECTOR_ID: ConnectorId = ConnectorId(2);
+
+/// Token accepted by the sandbox when it asks for MFA.
+pub const SANDBOX_MFA_TOKEN: &str = "123456";
+
+/// How often `run_scenario` polls the item while its execution runs.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const MAX_POLLS: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxScenario {
+    Success,
+    InvalidCredentials,
+    MfaToken,
+    MfaQrCode,
+    SiteNotAvailable,
+    AccountLocked,
+    AccountNeedsAction,
+    AlreadyLoggedIn,
+    UnexpectedError,
+}
+
+impl SandboxScenario {
+    pub const ALL: [SandboxScenario; 9] = [
+        Self::Success,
+        Self::InvalidCredentials,
+        Self::MfaToken,
+        Self::MfaQrCode,
+        Self::SiteNotAvailable,
+        Self::AccountLocked,
+        Self::AccountNeedsAction,
+        Self::AlreadyLoggedIn,
+        Self::UnexpectedError,
+    ];
+
+    pub fn user(&self) -> &'static str {
+        match self {
+            Self::Success | Self::InvalidCredentials => "user-ok",
+            Self::MfaToken => "user-mfa",
+            Self::MfaQrCode => "user-mfa-qr",
+            Self::SiteNotAvailable => "user-unavailable",
+            Self::AccountLocked => "user-locked",
+            Self::AccountNeedsAction => "user-action",
+            Self::AlreadyLoggedIn => "user-logged",
+            Self::UnexpectedError => "user-error",
+        }
+    }
+
+    pub fn password(&self) -> &'static str {
+        match self {
+            Self::InvalidCredentials => "password-incorrect",
+            _ => "password-ok",
+        }
+    }
+
+    /// Item parameters to create the sandbox item with.
+    pub fn parameters(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("user".to_string(), self.user().to_string()),
+            ("password".to_string(), self.password().to_string()),
+        ])
+    }
+
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Option<Self> {
+        let user = parameters.get("user")?;
+        let password = parameters.get("password")?;
+
+        Self::ALL
+            .into_iter()
+            .find(|scenario| scenario.user() == user && scenario.password() == password)
+    }
+
+    /// Error code the execution ends with, if any.
+    pub fn expected_error(&self) -> Option<ExecutionErrorCodes> {
+        match self {
+            Self::Success | Self::MfaToken | Self::MfaQrCode => None,
+            Self::InvalidCredentials => Some(ExecutionErrorCodes::InvalidCredentials),
+            Self::SiteNotAvailable => Some(ExecutionErrorCodes::SiteNotAvailable),
+            Self::AccountLocked => Some(ExecutionErrorCodes::AccountLocked),
+            Self::AccountNeedsAction => Some(ExecutionErrorCodes::AccountNeedsAction),
+            Self::AlreadyLoggedIn => Some(ExecutionErrorCodes::AlreadyLoggedIn),
+            Self::UnexpectedError => Some(ExecutionErrorCodes::UnexpectedError),
+        }
+    }
+
+    /// Execution status `run_scenario` stops at. The QR code scenario stops
+    /// waiting for the user to scan the code.
+    pub fn expected_execution_status(&self) -> ExecutionStatus {
+        match self {
+            Self::Success | Self::MfaToken => ExecutionStatus::Success,
+            Self::MfaQrCode => ExecutionStatus::WaitingUserInput,
+            _ => ExecutionStatus::Error,
+        }
+    }
+
+    /// Steps `testing::MockServer` walks items of this scenario through.
+    pub fn item_script(&self) -> Vec<ItemStep> {
+        let login = ItemStep::new(ItemStatus::Updating, ExecutionStatus::LoginInProgress);
+        let accounts = ItemStep::new(ItemStatus::Updating, ExecutionStatus::AccountsInProgress);
+
+        match self {
+            Self::Success => vec![login, accounts, ItemStep::success()],
+            Self::MfaToken => vec![
+                login,
+                ItemStep::waiting_for_token(),
+                ItemStep::new(ItemStatus::Updating, ExecutionStatus::LoginMfaInProgress),
+                accounts,
+                ItemStep::success(),
+            ],
+            Self::MfaQrCode => vec![
+                login,
+                ItemStep::new(
+                    ItemStatus::WaitingUserInput,
+                    ExecutionStatus::WaitingUserInput,
+                )
+                .with_parameter(json!({
+                    "label": "QR code",
+                    "name": "qr",
+                    "type": "image",
+                    "mfa": true,
+                    "data": "data:image/png;base64,",
+                })),
+            ],
+            _ => vec![
+                login,
+                ItemStep::error(
+                    self.expected_error()
+                        .unwrap_or(ExecutionErrorCodes::UnexpectedError),
+                ),
+            ],
+        }
+    }
+}
+
+/// Creates a sandbox item for `scenario` and polls it until its execution
+/// stops, sending `SANDBOX_MFA_TOKEN` when the sandbox asks for a token.
+pub async fn run_scenario(
+    client: &Client,
+    api_key: &str,
+    scenario: SandboxScenario,
+    poll_interval: Duration,
+) -> Result<Item, Box<dyn std::error::Error>> {
+    let mut item = client
+        .create_item(api_key, PLUGGY_BANK_CONNECTOR_ID, &scenario.parameters())
+        .await?;
+
+    for _ in 0..MAX_POLLS {
+        if is_waiting_for_token(&item) {
+            let token = HashMap::from([("token".to_string(), SANDBOX_MFA_TOKEN.to_string())]);
+            item = client
+                .update_item_mfa_credentials(api_key, item.id, &token)
+                .await?;
+        } else if has_stopped(&item) {
+            return Ok(item);
+        } else {
+            tokio::time::sleep(poll_interval).await;
+            item = client.get_item(api_key, item.id).await?;
+        }
+    }
+
+    Err(format!("Item {} did not stop after {} polls", item.id, MAX_POLLS).into())
+}
+
+/// Runs `scenario` and panics unless the item ends with the expected
+/// execution status and error code.
+pub async fn assert_scenario(client: &Client, api_key: &str, scenario: SandboxScenario) -> Item {
+    assert_scenario_with_interval(client, api_key, scenario, DEFAULT_POLL_INTERVAL).await
+}
+
+pub async fn assert_scenario_with_interval(
+    client: &Client,
+    api_key: &str,
+    scenario: SandboxScenario,
+    poll_interval: Duration,
+) -> Item {
+    let item = run_scenario(client, api_key, scenario, poll_interval)
+        .await
+        .unwrap_or_else(|error| panic!("{:?} failed: {}", scenario, error));
+
+    let error = item.error.as_ref().map(|error| &error.code);
+    assert_eq!(
+        error,
+        scenario.expected_error().as_ref(),
+        "{:?} ended with an unexpected error code",
+        scenario
+    );
+    assert_eq!(
+        item.execution_status,
+        scenario.expected_execution_status(),
+        "{:?} ended with an unexpected execution status",
+        scenario
+    );
+
+    item
+}
+
+fn is_waiting_for_token(item: &Item) -> bool {
+    matches!(item.execution_status, ExecutionStatus::WaitingUserInput)
+        && item
+            .parameter
+            .as_ref()
+            .is_some_and(|parameter| parameter.name == "token")
+}
+
+fn has_stopped(item: &Item) -> bool {
+    item.execution_status.is_terminal() || item.execution_status.is_waiting_for_user()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockServer;
+    use crate::tests::test_client;
+
+    #[tokio::test]
+    async fn can_run_every_scenario_against_mock_server() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        for scenario in SandboxScenario::ALL {
+            let item =
+                assert_scenario_with_interval(&client, &api_key, scenario, Duration::ZERO).await;
+            assert_eq!(item.connector.id, PLUGGY_BANK_CONNECTOR_ID);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Pluggy credentials or a recorded cassette"]
+    async fn can_run_success_scenario() {
+        let (client, api_key) = test_client("can_run_success_scenario").await;
+
+        let item = assert_scenario(&client, &api_key, SandboxScenario::Success).await;
+        assert_eq!(item.connector.id, PLUGGY_BANK_CONNECTOR_ID);
+        client.delete_item(&api_key, item.id).await.unwrap();
+    }
+
+    #[test]
+    fn can_find_scenario_from_parameters() {
+        for scenario in SandboxScenario::ALL {
+            assert_eq!(
+                SandboxScenario::from_parameters(&scenario.parameters()),
+                Some(scenario)
+            );
+        }
+
+        let mut parameters = SandboxScenario::Success.parameters();
+        parameters.insert("password".to_string(), "other".to_string());
+        assert_eq!(SandboxScenario::from_parameters(&parameters), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Metrics hooks.
+//!
+//! A `MetricsHook` set with `ClientBuilder::metrics_hook` is told about every
+//! request the client sends and every item it receives, to feed Prometheus,
+//! OpenTelemetry or any other metrics system. With the `metrics` feature,
+//! `MetricsRecorder` reports them through the `metrics` crate:
+//!
+//! - `pluggy_requests_total` counter, by `endpoint`, `method` and `status`.
+//! - `pluggy_request_errors_total` counter of transport errors and 4xx/5xx
+//!   responses, by `endpoint`, `method` and `status`.
+//! - `pluggy_request_duration_seconds` histogram, by `endpoint` and `method`.
+//! - `pluggy_item_errors_total` counter, by `connector_id` and `code`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::{Method, StatusCode};
+
+use crate::middleware::{Middleware, Next};
+use crate::transport::{endpoint, HttpRequest, TransportFuture};
+use crate::{ConnectorId, ExecutionErrorCodes, ExecutionStatus, Item, ItemId};
+
+/// A request the client sent, retries included.
+#[derive(Debug)]
+pub struct RequestEvent<'a> {
+    /// Path with ids replaced by `{id}`, such as `/items/{id}/mfa`.
+    pub endpoint: &'a str,
+    pub method: &'a Method,
+    /// `None` when the request failed before a response arrived.
+    pub status: Option<StatusCode>,
+    pub latency: Duration,
+}
+
+impl RequestEvent<'_> {
+    pub fn is_error(&self) -> bool {
+        self.status
+            .is_none_or(|status| status.is_client_error() || status.is_server_error())
+    }
+}
+
+/// An item returned by the API. Items are reported when their execution
+/// status or error changes, so polling an item that failed reports its error
+/// once.
+#[derive(Debug)]
+pub struct ItemEvent<'a> {
+    pub item_id: ItemId,
+    pub connector_id: ConnectorId,
+    pub execution_status: &'a ExecutionStatus,
+    pub error: Option<&'a ExecutionErrorCodes>,
+}
+
+pub trait MetricsHook: Send + Sync {
+    fn on_request(&self, event: &RequestEvent<'_>);
+
+    fn on_item(&self, _event: &ItemEvent<'_>) {}
+}
+
+impl<T: MetricsHook + ?Sized> MetricsHook for Arc<T> {
+    fn on_request(&self, event: &RequestEvent<'_>) {
+        (**self).on_request(event)
+    }
+
+    fn on_item(&self, event: &ItemEvent<'_>) {
+        (**self).on_item(event)
+    }
+}
+
+/// Reports items to a `MetricsHook`, skipping those received again with the
+/// same execution status and error.
+pub(crate) struct ItemReporter {
+    hook: Arc<dyn MetricsHook>,
+    last_reported: Mutex<HashMap<ItemId, (ExecutionStatus, Option<ExecutionErrorCodes>)>>,
+}
+
+impl ItemReporter {
+    pub(crate) fn new(hook: Arc<dyn MetricsHook>) -> Self {
+        Self {
+            hook,
+            last_reported: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn report(&self, item: &Item) {
+        let error = item.error.as_ref().map(|error| &error.code);
+        let state = (item.execution_status.clone(), error.cloned());
+        if self
+            .last_reported
+            .lock()
+            .unwrap()
+            .insert(item.id, state.clone())
+            == Some(state)
+        {
+            return;
+        }
+
+        self.hook.on_item(&ItemEvent {
+            item_id: item.id,
+            connector_id: item.connector.id,
+            execution_status: &item.execution_status,
+            error,
+        });
+    }
+}
+
+/// Middleware reporting every request to a `MetricsHook`.
+pub struct MetricsMiddleware {
+    hook: Arc<dyn MetricsHook>,
+}
+
+impl MetricsMiddleware {
+    pub fn new(hook: Arc<dyn MetricsHook>) -> Self {
+        Self { hook }
+    }
+}
+
+impl Middleware for MetricsMiddleware {
+    fn handle<'a>(&'a self, request: HttpRequest, next: Next<'a>) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let endpoint = endpoint(request.uri().path());
+            let method = request.method().clone();
+
+            let started_at = Instant::now();
+            let result = next.run(request).await;
+
+            self.hook.on_request(&RequestEvent {
+                endpoint: &endpoint,
+                method: &method,
+                status: result.as_ref().ok().map(|response| response.status()),
+                latency: started_at.elapsed(),
+            });
+
+            result
+        })
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub use self::recorder::MetricsRecorder;
+
+#[cfg(feature = "metrics")]
+mod recorder {
+    use super::{ItemEvent, MetricsHook, RequestEvent};
+
+    /// Reports to the recorder installed in the `metrics` crate.
+    #[derive(Debug, Clone, Default)]
+    pub struct MetricsRecorder;
+
+    impl MetricsHook for MetricsRecorder {
+        fn on_request(&self, event: &RequestEvent<'_>) {
+            let status = event
+                .status
+                .map(|status| status.as_u16().to_string())
+                .unwrap_or_else(|| "error".to_string());
+            let labels = [
+                ("endpoint", event.endpoint.to_string()),
+                ("method", event.method.to_string()),
+            ];
+
+            metrics::histogram!("pluggy_request_duration_seconds", &labels)
+                .record(event.latency.as_secs_f64());
+
+            let labels = [labels[0].clone(), labels[1].clone(), ("status", status)];
+            metrics::counter!("pluggy_requests_total", &labels).increment(1);
+            if event.is_error() {
+                metrics::counter!("pluggy_request_errors_total", &labels).increment(1);
+            }
+        }
+
+        fn on_item(&self, event: &ItemEvent<'_>) {
+            if let Some(code) = event.error {
+                metrics::counter!(
+                    "pluggy_item_errors_total",
+                    "connector_id" => event.connector_id.to_string(),
+                    "code" => code.to_string(),
+                )
+                .increment(1);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "hyper", feature = "metrics")))]
+mod tests {
+    #[cfg(feature = "hyper")]
+    use std::sync::Mutex;
+
+    use super::*;
+    #[cfg(feature = "hyper")]
+    use crate::testing::{ItemStep, MockServer};
+    #[cfg(feature = "hyper")]
+    use crate::Client;
+
+    #[cfg(feature = "hyper")]
+    #[derive(Default)]
+    struct RecordingHook {
+        requests: Mutex<Vec<(String, Method, Option<StatusCode>)>>,
+        item_errors: Mutex<Vec<(ConnectorId, ExecutionErrorCodes)>>,
+    }
+
+    #[cfg(feature = "hyper")]
+    impl MetricsHook for RecordingHook {
+        fn on_request(&self, event: &RequestEvent<'_>) {
+            self.requests.lock().unwrap().push((
+                event.endpoint.to_string(),
+                event.method.clone(),
+                event.status,
+            ));
+        }
+
+        fn on_item(&self, event: &ItemEvent<'_>) {
+            if let Some(code) = event.error {
+                self.item_errors
+                    .lock()
+                    .unwrap()
+                    .push((event.connector_id, code.clone()));
+            }
+        }
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn can_report_requests_and_item_errors() {
+        let server = MockServer::start().await;
+        server.script_connector_items(
+            ConnectorId(201),
+            vec![ItemStep::error(ExecutionErrorCodes::SiteNotAvailable)],
+        );
+        let hook = Arc::new(RecordingHook::default());
+        let client = Client::builder(
+            crate::testing::MOCK_CLIENT_ID.to_string(),
+            crate::testing::MOCK_CLIENT_SECRET.to_string(),
+        )
+        .base_url(server.url())
+        .transport(crate::transport::HyperTransport::http())
+        .metrics_hook(hook.clone())
+        .build();
+
+        let api_key = client.create_api_key().await.unwrap();
+        let parameters = crate::sandbox::SandboxScenario::Success.parameters();
+        let item = client
+            .create_item(&api_key, ConnectorId(201), &parameters)
+            .await
+            .unwrap();
+        client.get_item(&api_key, item.id).await.unwrap();
+        client.get_item(&api_key, item.id).await.unwrap();
+        assert!(client
+            .get_connector(&api_key, ConnectorId(999))
+            .await
+            .is_err());
+
+        let requests = hook.requests.lock().unwrap();
+        assert_eq!(
+            *requests,
+            vec![
+                ("/auth".to_string(), Method::POST, Some(StatusCode::OK)),
+                ("/items".to_string(), Method::POST, Some(StatusCode::OK)),
+                ("/items/{id}".to_string(), Method::GET, Some(StatusCode::OK)),
+                ("/items/{id}".to_string(), Method::GET, Some(StatusCode::OK)),
+                (
+                    "/connectors/{id}".to_string(),
+                    Method::GET,
+                    Some(StatusCode::NOT_FOUND)
+                ),
+            ]
+        );
+        assert_eq!(
+            *hook.item_errors.lock().unwrap(),
+            vec![(ConnectorId(201), ExecutionErrorCodes::SiteNotAvailable)]
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn can_record_with_metrics_crate() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            MetricsRecorder.on_request(&RequestEvent {
+                endpoint: "/items/{id}",
+                method: &Method::GET,
+                status: Some(StatusCode::SERVICE_UNAVAILABLE),
+                latency: Duration::from_millis(250),
+            });
+            MetricsRecorder.on_item(&ItemEvent {
+                item_id: ItemId(uuid::Uuid::nil()),
+                connector_id: ConnectorId(201),
+                execution_status: &ExecutionStatus::Error,
+                error: Some(&ExecutionErrorCodes::InvalidCredentials),
+            });
+        });
+
+        let metrics: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let labels: Vec<String> = key
+                    .key()
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect();
+                (key.key().name().to_string(), labels.join(","), value)
+            })
+            .collect();
+
+        let find = |name: &str| {
+            metrics
+                .iter()
+                .find(|(metric, _, _)| metric == name)
+                .unwrap_or_else(|| panic!("{} was not recorded", name))
+        };
+        assert_eq!(
+            find("pluggy_request_errors_total").1,
+            "endpoint=/items/{id},method=GET,status=503"
+        );
+        assert_eq!(find("pluggy_requests_total").2, DebugValue::Counter(1));
+        assert!(matches!(
+            find("pluggy_request_duration_seconds").2,
+            DebugValue::Histogram(_)
+        ));
+        assert_eq!(
+            find("pluggy_item_errors_total").1,
+            "connector_id=201,code=INVALID_CREDENTIALS"
+        );
+    }
+}
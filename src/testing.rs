@@ -0,0 +1,1194 @@
+//! In-memory Pluggy API for tests, enabled with the `test-util` feature.
+//!
+//! `MockServer` serves `/auth`, `/connect_token`, `/connectors`, `/items`,
+//! `/webhooks`, `/categories`, `/transactions`, `/smart-transfers`,
+//! `/boleto-connections`, `/boletos`, `/payments/requests` and `/consents`
+//! from local state, so tests can run offline:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use pluggy::testing::{ItemStep, MockServer};
+//! use pluggy::{ConnectorId, ExecutionStatus, ItemStatus};
+//!
+//! let server = MockServer::start().await;
+//! server.script_connector_items(
+//!     ConnectorId(2),
+//!     vec![ItemStep::new(ItemStatus::Updated, ExecutionStatus::Success)],
+//! );
+//!
+//! let (client, api_key) = server.client_with_api_key().await?;
+//! let connector = client.get_connector(&api_key, ConnectorId(2)).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Items created through the server walk through their scripted steps, one
+//! step every time they are fetched. Steps waiting for user input only move
+//! on once MFA credentials are sent. Sandbox items without a script follow
+//! their `sandbox::SandboxScenario`.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use url::form_urlencoded;
+
+use crate::sandbox::{SandboxScenario, PLUGGY_BANK_CONNECTOR_ID};
+use crate::transport::HyperTransport;
+use crate::{
+    BoletoStatus, Client, ConnectorId, ConnectorStatus, DigitableLine, ExecutionErrorCodes,
+    ExecutionStatus, ItemId, ItemStatus, PaymentRequestStatus, SmartTransferPaymentStatus,
+    SmartTransferPreauthorizationStatus,
+};
+
+pub const MOCK_CLIENT_ID: &str = "mock-client-id";
+pub const MOCK_CLIENT_SECRET: &str = "mock-client-secret";
+/// Payment recipient the server starts with.
+pub const MOCK_RECIPIENT_ID: &str = "00000000-0000-4000-9000-000000000001";
+
+const MOCK_DATE: &str = "2023-01-01T00:00:00.000Z";
+const MOCK_PAGE_SIZE: usize = 20;
+
+/// One state an item goes through while its execution runs.
+#[derive(Debug, Clone)]
+pub struct ItemStep {
+    pub status: ItemStatus,
+    pub execution_status: ExecutionStatus,
+    pub error: Option<ExecutionErrorCodes>,
+    /// Credential asked to the user, for steps waiting for user input.
+    pub parameter: Option<Value>,
+}
+
+impl ItemStep {
+    pub fn new(status: ItemStatus, execution_status: ExecutionStatus) -> Self {
+        Self {
+            status,
+            execution_status,
+            error: None,
+            parameter: None,
+        }
+    }
+
+    pub fn with_error(mut self, code: ExecutionErrorCodes) -> Self {
+        self.error = Some(code);
+        self
+    }
+
+    pub fn with_parameter(mut self, parameter: Value) -> Self {
+        self.parameter = Some(parameter);
+        self
+    }
+
+    /// Execution finishing successfully.
+    pub fn success() -> Self {
+        Self::new(ItemStatus::Updated, ExecutionStatus::Success)
+    }
+
+    /// Execution failing with the given error code.
+    pub fn error(code: ExecutionErrorCodes) -> Self {
+        let status = match code {
+            ExecutionErrorCodes::InvalidCredentials
+            | ExecutionErrorCodes::InvalidCredentialsMfa
+            | ExecutionErrorCodes::AccountLocked
+            | ExecutionErrorCodes::AccountCredentialsReset
+            | ExecutionErrorCodes::AccountNeedsAction
+            | ExecutionErrorCodes::AlreadyLoggedIn => ItemStatus::LoginError,
+            _ => ItemStatus::Outdated,
+        };
+
+        Self::new(status, ExecutionStatus::Error).with_error(code)
+    }
+
+    /// Execution waiting for an MFA token.
+    pub fn waiting_for_token() -> Self {
+        Self::new(
+            ItemStatus::WaitingUserInput,
+            ExecutionStatus::WaitingUserInput,
+        )
+        .with_parameter(json!({
+            "label": "Token",
+            "name": "token",
+            "type": "number",
+            "mfa": true,
+            "placeholder": "123456",
+        }))
+    }
+
+    fn is_waiting_user_input(&self) -> bool {
+        matches!(self.execution_status, ExecutionStatus::WaitingUserInput)
+    }
+}
+
+/// Steps used when no script matches the item being created.
+pub fn default_item_script() -> Vec<ItemStep> {
+    vec![
+        ItemStep::new(ItemStatus::Updating, ExecutionStatus::LoginInProgress),
+        ItemStep::new(ItemStatus::Updating, ExecutionStatus::AccountsInProgress),
+        ItemStep::new(
+            ItemStatus::Updating,
+            ExecutionStatus::TransactionsInProgress,
+        ),
+        ItemStep::success(),
+    ]
+}
+
+type ScriptMatcher = Box<dyn Fn(ConnectorId, &HashMap<String, String>) -> bool + Send>;
+
+struct MockItem {
+    json: Value,
+    steps: VecDeque<ItemStep>,
+    current: Option<ItemStep>,
+}
+
+struct MockState {
+    api_keys: Vec<String>,
+    connectors: Vec<(Value, bool)>,
+    items: HashMap<String, MockItem>,
+    webhooks: Vec<Value>,
+    categories: Vec<Value>,
+    category_rules: Vec<Value>,
+    transactions: Vec<Value>,
+    recipients: Vec<Value>,
+    preauthorizations: Vec<Value>,
+    smart_transfer_payments: Vec<Value>,
+    boleto_connections: Vec<Value>,
+    boletos: Vec<Value>,
+    payment_requests: Vec<Value>,
+    consents: Vec<Value>,
+    scripts: Vec<(ScriptMatcher, Vec<ItemStep>)>,
+    next_id: u64,
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port, seeded with the Itaú (201)
+    /// and sandbox Pluggy Bank (2) connectors, a few categories and the
+    /// `MOCK_RECIPIENT_ID` payment recipient.
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            api_keys: Vec::new(),
+            connectors: vec![(itau_connector(), false), (pluggy_bank_connector(), true)],
+            items: HashMap::new(),
+            webhooks: Vec::new(),
+            categories: default_categories(),
+            category_rules: Vec::new(),
+            transactions: Vec::new(),
+            recipients: vec![default_recipient()],
+            preauthorizations: Vec::new(),
+            smart_transfer_payments: Vec::new(),
+            boleto_connections: Vec::new(),
+            boletos: Vec::new(),
+            payment_requests: Vec::new(),
+            consents: Vec::new(),
+            scripts: Vec::new(),
+            next_id: 1,
+        }));
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(&state, request).await) }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            shutdown_signal.await.ok();
+        }));
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client pointed to this server, authenticating with the mock credentials.
+    pub fn client(&self) -> Client {
+        Client::builder(MOCK_CLIENT_ID.to_string(), MOCK_CLIENT_SECRET.to_string())
+            .base_url(self.url())
+            .transport(HyperTransport::http())
+            .build()
+    }
+
+    pub async fn client_with_api_key(
+        &self,
+    ) -> Result<(Client, String), Box<dyn std::error::Error>> {
+        let client = self.client();
+        let api_key = client.create_api_key().await?;
+        Ok((client, api_key))
+    }
+
+    /// Adds a connector, given as its API JSON representation.
+    pub fn add_connector(&self, connector: Value, sandbox: bool) {
+        self.state().connectors.push((connector, sandbox));
+    }
+
+    /// Changes the health status reported for a connector.
+    pub fn set_connector_health(&self, connector_id: ConnectorId, status: ConnectorStatus) {
+        let mut state = self.state();
+        let connector = state
+            .connectors
+            .iter_mut()
+            .map(|(connector, _)| connector)
+            .find(|connector| connector["id"].as_i64() == Some(connector_id.0.into()));
+        if let Some(connector) = connector {
+            connector["health"]["status"] = json!(status);
+        }
+    }
+
+    pub fn add_category(&self, category: Value) {
+        self.state().categories.push(category);
+    }
+
+    /// Adds a transaction, given as its API JSON representation.
+    pub fn add_transaction(&self, transaction: Value) {
+        self.state().transactions.push(transaction);
+    }
+
+    /// Adds a payment recipient, given as its API JSON representation.
+    pub fn add_recipient(&self, recipient: Value) {
+        self.state().recipients.push(recipient);
+    }
+
+    /// Adds an Open Finance consent, given as its API JSON representation.
+    pub fn add_consent(&self, consent: Value) {
+        self.state().consents.push(consent);
+    }
+
+    /// Completes a smart transfer preauthorization, as if the user granted
+    /// consent through its `consentUrl`.
+    pub fn grant_smart_transfer_consent(&self, preauthorization_id: &str) {
+        let mut state = self.state();
+        let preauthorization = state
+            .preauthorizations
+            .iter_mut()
+            .find(|preauthorization| preauthorization["id"] == preauthorization_id);
+        if let Some(preauthorization) = preauthorization {
+            preauthorization["status"] = json!(SmartTransferPreauthorizationStatus::Completed);
+        }
+    }
+
+    /// Items created with a connector and parameters accepted by `matcher`
+    /// walk through `steps`. Scripts are checked in the order they were added.
+    pub fn script_items<F>(&self, matcher: F, steps: Vec<ItemStep>)
+    where
+        F: Fn(ConnectorId, &HashMap<String, String>) -> bool + Send + 'static,
+    {
+        self.state().scripts.push((Box::new(matcher), steps));
+    }
+
+    pub fn script_connector_items(&self, connector_id: ConnectorId, steps: Vec<ItemStep>) {
+        self.script_items(move |id, _| id == connector_id, steps);
+    }
+
+    /// Current JSON representation of an item, without advancing its script.
+    pub fn item(&self, item_id: ItemId) -> Option<Value> {
+        self.state()
+            .items
+            .get(&item_id.to_string())
+            .map(|item| item.json.clone())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+impl MockState {
+    fn next_id(&mut self) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+        format!("00000000-0000-4000-8000-{:012x}", id)
+    }
+
+    fn connector(&self, connector_id: i64) -> Option<&Value> {
+        self.connectors
+            .iter()
+            .map(|(connector, _)| connector)
+            .find(|connector| connector["id"].as_i64() == Some(connector_id))
+    }
+
+    fn script_for(
+        &self,
+        connector_id: ConnectorId,
+        parameters: &HashMap<String, String>,
+    ) -> Vec<ItemStep> {
+        self.scripts
+            .iter()
+            .find(|(matcher, _)| matcher(connector_id, parameters))
+            .map(|(_, steps)| steps.clone())
+            .or_else(|| {
+                SandboxScenario::from_parameters(parameters)
+                    .filter(|_| connector_id == PLUGGY_BANK_CONNECTOR_ID)
+                    .map(|scenario| scenario.item_script())
+            })
+            .unwrap_or_else(default_item_script)
+    }
+}
+
+impl MockItem {
+    fn apply(&mut self, step: ItemStep) {
+        let json = &mut self.json;
+        json["status"] = json!(step.status);
+        json["executionStatus"] = json!(step.execution_status);
+        json["parameter"] = step.parameter.clone().unwrap_or(Value::Null);
+        json["error"] = match &step.error {
+            Some(code) => json!({ "code": code, "message": format!("{:?}", code) }),
+            None => Value::Null,
+        };
+
+        match step.error {
+            Some(ExecutionErrorCodes::InvalidCredentials) => {
+                let attempts = json["consecutiveFailedLoginAttempts"].as_i64().unwrap_or(0);
+                json["consecutiveFailedLoginAttempts"] = json!(attempts + 1);
+            }
+            None if step.status == ItemStatus::Updated => {
+                json["consecutiveFailedLoginAttempts"] = json!(0);
+                json["lastUpdatedAt"] = json!(MOCK_DATE);
+            }
+            _ => {}
+        }
+
+        self.current = Some(step);
+    }
+
+    /// Moves to the next scripted step, unless waiting for the user.
+    fn advance(&mut self) {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(ItemStep::is_waiting_user_input)
+        {
+            return;
+        }
+        if let Some(step) = self.steps.pop_front() {
+            self.apply(step);
+        }
+    }
+
+    fn restart(&mut self, steps: Vec<ItemStep>) {
+        self.steps = steps.into();
+        self.current = None;
+        self.json["status"] = json!(ItemStatus::Updating);
+        self.json["executionStatus"] = json!(ExecutionStatus::Created);
+        self.json["error"] = Value::Null;
+        self.json["parameter"] = Value::Null;
+    }
+}
+
+async fn handle(state: &Mutex<MockState>, request: Request<Body>) -> Response<Body> {
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Invalid body"),
+    };
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let query: HashMap<String, String> = parts
+        .uri
+        .query()
+        .map(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let segments: Vec<&str> = parts
+        .uri
+        .path()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut state = state.lock().unwrap();
+
+    if segments == ["auth"] && parts.method == Method::POST {
+        return authenticate(&mut state, &body);
+    }
+
+    let api_key = parts
+        .headers
+        .get("X-API-KEY")
+        .and_then(|value| value.to_str().ok());
+    if !api_key.is_some_and(|api_key| state.api_keys.iter().any(|key| key == api_key)) {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "Missing or invalid authorization token",
+        );
+    }
+
+    match (&parts.method, segments.as_slice()) {
+        (&Method::POST, ["connect_token"]) => {
+            json_response(StatusCode::OK, json!({ "accessToken": state.next_id() }))
+        }
+        (&Method::GET, ["connectors"]) => get_connectors(&state, &query),
+        (&Method::GET, ["connectors", id]) => {
+            match id.parse().ok().and_then(|id| state.connector(id)) {
+                Some(connector) => json_response(StatusCode::OK, connector.clone()),
+                None => not_found("Connector"),
+            }
+        }
+        (&Method::POST, ["connectors", id, "validate"]) => {
+            match id.parse().ok().and_then(|id| state.connector(id)) {
+                Some(connector) => validate_parameters(connector, &body),
+                None => not_found("Connector"),
+            }
+        }
+        (&Method::POST, ["items"]) => create_item(&mut state, &body),
+        (&Method::GET, ["items", id]) => match state.items.get_mut(*id) {
+            Some(item) => {
+                item.advance();
+                json_response(StatusCode::OK, item.json.clone())
+            }
+            None => not_found("Item"),
+        },
+        (&Method::PATCH, ["items", id]) => {
+            let connector_id = match state.items.get(*id) {
+                Some(item) => {
+                    ConnectorId(item.json["connector"]["id"].as_i64().unwrap_or_default() as i32)
+                }
+                None => return not_found("Item"),
+            };
+            let steps = state.script_for(connector_id, &parameters(&body));
+            let item = state.items.get_mut(*id).unwrap();
+            item.restart(steps);
+            json_response(StatusCode::OK, item.json.clone())
+        }
+        (&Method::PATCH, ["items", id, "mfa"]) => match state.items.get_mut(*id) {
+            Some(item) => {
+                item.current = None;
+                item.advance();
+                json_response(StatusCode::OK, item.json.clone())
+            }
+            None => not_found("Item"),
+        },
+        (&Method::DELETE, ["items", id]) => match state.items.remove(*id) {
+            Some(_) => json_response(StatusCode::OK, json!({})),
+            None => not_found("Item"),
+        },
+        (&Method::GET, ["webhooks"]) => page_response(state.webhooks.clone(), &query),
+        (&Method::POST, ["webhooks"]) => {
+            let webhook = json!({
+                "id": state.next_id(),
+                "url": body["url"],
+                "event": body["event"],
+                "createdAt": MOCK_DATE,
+                "updatedAt": MOCK_DATE,
+                "disabledAt": null,
+            });
+            state.webhooks.push(webhook.clone());
+            json_response(StatusCode::OK, webhook)
+        }
+        (&Method::GET, ["webhooks", id]) => {
+            match state.webhooks.iter().find(|webhook| webhook["id"] == *id) {
+                Some(webhook) => json_response(StatusCode::OK, webhook.clone()),
+                None => not_found("Webhook"),
+            }
+        }
+        (&Method::DELETE, ["webhooks", id]) => {
+            let count = state.webhooks.len();
+            state.webhooks.retain(|webhook| webhook["id"] != *id);
+            if state.webhooks.len() == count {
+                return not_found("Webhook");
+            }
+            json_response(StatusCode::OK, json!({}))
+        }
+        (&Method::GET, ["categories"]) => page_response(state.categories.clone(), &query),
+        (&Method::GET, ["categories", "rules"]) => {
+            page_response(state.category_rules.clone(), &query)
+        }
+        (&Method::POST, ["categories", "rules"]) => {
+            if body["categoryId"]
+                .as_str()
+                .and_then(|id| find(&state.categories, id))
+                .is_none()
+            {
+                return not_found("Category");
+            }
+            let rule = json!({
+                "id": state.next_id(),
+                "description": body["description"],
+                "categoryId": body["categoryId"],
+                "createdAt": MOCK_DATE,
+            });
+            state.category_rules.push(rule.clone());
+            json_response(StatusCode::OK, rule)
+        }
+        (&Method::DELETE, ["categories", "rules", id]) => {
+            let count = state.category_rules.len();
+            state.category_rules.retain(|rule| rule["id"] != *id);
+            if state.category_rules.len() == count {
+                return not_found("Category rule");
+            }
+            json_response(StatusCode::OK, json!({}))
+        }
+        (&Method::GET, ["categories", id]) => {
+            match state
+                .categories
+                .iter()
+                .find(|category| category["id"] == *id)
+            {
+                Some(category) => json_response(StatusCode::OK, category.clone()),
+                None => not_found("Category"),
+            }
+        }
+        (&Method::PATCH, ["transactions", id]) => {
+            let category = match body["categoryId"]
+                .as_str()
+                .and_then(|id| find(&state.categories, id))
+            {
+                Some(category) => category.clone(),
+                None => return not_found("Category"),
+            };
+            let transaction = match state
+                .transactions
+                .iter_mut()
+                .find(|transaction| transaction["id"] == *id)
+            {
+                Some(transaction) => transaction,
+                None => return not_found("Transaction"),
+            };
+            transaction["categoryId"] = category["id"].clone();
+            transaction["category"] = category["description"].clone();
+            json_response(StatusCode::OK, transaction.clone())
+        }
+        (&Method::GET, ["smart-transfers", "preauthorizations"]) => {
+            page_response(state.preauthorizations.clone(), &query)
+        }
+        (&Method::POST, ["smart-transfers", "preauthorizations"]) => {
+            create_preauthorization(&mut state, &body)
+        }
+        (&Method::GET, ["smart-transfers", "preauthorizations", id]) => {
+            match find(&state.preauthorizations, id) {
+                Some(preauthorization) => json_response(StatusCode::OK, preauthorization.clone()),
+                None => not_found("Preauthorization"),
+            }
+        }
+        (&Method::POST, ["smart-transfers", "payments"]) => {
+            create_smart_transfer_payment(&mut state, &body)
+        }
+        (&Method::GET, ["smart-transfers", "payments", id]) => {
+            match find(&state.smart_transfer_payments, id) {
+                Some(payment) => json_response(StatusCode::OK, payment.clone()),
+                None => not_found("Payment"),
+            }
+        }
+        (&Method::POST, ["boleto-connections"]) => create_boleto_connection(&mut state, &body),
+        (&Method::POST, ["boletos"]) => issue_boleto(&mut state, &body),
+        (&Method::GET, ["boletos", id]) => match find(&state.boletos, id) {
+            Some(boleto) => json_response(StatusCode::OK, boleto.clone()),
+            None => not_found("Boleto"),
+        },
+        (&Method::POST, ["boletos", id, "cancel"]) => {
+            let boleto = match state.boletos.iter_mut().find(|boleto| boleto["id"] == *id) {
+                Some(boleto) => boleto,
+                None => return not_found("Boleto"),
+            };
+            if ![json!(BoletoStatus::Open), json!(BoletoStatus::Overdue)]
+                .contains(&boleto["status"])
+            {
+                return error_response(StatusCode::BAD_REQUEST, "Boleto can't be cancelled");
+            }
+            boleto["status"] = json!(BoletoStatus::Cancelled);
+            json_response(StatusCode::OK, boleto.clone())
+        }
+        (&Method::POST, ["payments", "requests"]) => create_payment_request(&mut state, &body),
+        (&Method::GET, ["consents"]) => {
+            let consents = state
+                .consents
+                .iter()
+                .filter(|consent| {
+                    query
+                        .get("itemId")
+                        .is_none_or(|item_id| consent["itemId"] == item_id.as_str())
+                })
+                .cloned()
+                .collect();
+            page_response(consents, &query)
+        }
+        (&Method::GET, ["consents", id]) => match find(&state.consents, id) {
+            Some(consent) => json_response(StatusCode::OK, consent.clone()),
+            None => not_found("Consent"),
+        },
+        (&Method::POST, ["consents", id, "revoke"]) => {
+            let consent = match state
+                .consents
+                .iter_mut()
+                .find(|consent| consent["id"] == *id)
+            {
+                Some(consent) => consent,
+                None => return not_found("Consent"),
+            };
+            if !consent["revokedAt"].is_null() {
+                return error_response(StatusCode::BAD_REQUEST, "Consent is already revoked");
+            }
+            consent["revokedAt"] = json!(MOCK_DATE);
+            json_response(StatusCode::OK, consent.clone())
+        }
+        _ => not_found("Route"),
+    }
+}
+
+fn authenticate(state: &mut MockState, body: &Value) -> Response<Body> {
+    if body["clientId"] != MOCK_CLIENT_ID || body["clientSecret"] != MOCK_CLIENT_SECRET {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid credentials");
+    }
+
+    let api_key = format!("mock-api-key-{}", state.next_id());
+    state.api_keys.push(api_key.clone());
+    json_response(StatusCode::OK, json!({ "apiKey": api_key }))
+}
+
+fn get_connectors(state: &MockState, query: &HashMap<String, String>) -> Response<Body> {
+    let with_sandbox = query
+        .get("sandbox")
+        .is_some_and(|sandbox| sandbox == "true");
+    let list = |name: &str| -> Option<Vec<String>> {
+        query
+            .get(name)
+            .map(|values| values.split(',').map(str::to_string).collect())
+    };
+    let countries = list("countries");
+    let types = list("types");
+
+    let connectors = state
+        .connectors
+        .iter()
+        .filter(|(_, sandbox)| with_sandbox || !sandbox)
+        .map(|(connector, _)| connector)
+        .filter(|connector| {
+            let name = connector["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_lowercase();
+            let matches_list = |values: &Option<Vec<String>>, field: &str| {
+                values.as_ref().is_none_or(|values| {
+                    values
+                        .iter()
+                        .any(|value| connector[field] == value.as_str())
+                })
+            };
+
+            query
+                .get("name")
+                .is_none_or(|query| name.contains(&query.to_lowercase()))
+                && matches_list(&countries, "country")
+                && matches_list(&types, "type")
+                && query.get("isOpenFinance").is_none_or(|is_open_finance| {
+                    connector["isOpenFinance"]
+                        .as_bool()
+                        .unwrap_or(false)
+                        .to_string()
+                        == *is_open_finance
+                })
+        })
+        .cloned()
+        .collect();
+
+    page_response(connectors, query)
+}
+
+fn validate_parameters(connector: &Value, body: &Value) -> Response<Body> {
+    let errors: Vec<Value> = connector["credentials"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|credential| {
+            !credential["optional"].as_bool().unwrap_or(false)
+                && !credential["mfa"].as_bool().unwrap_or(false)
+        })
+        .filter_map(|credential| credential["name"].as_str())
+        .filter(|name| body[name].as_str().is_none_or(str::is_empty))
+        .map(|name| {
+            json!({
+                "code": "001",
+                "message": format!("{} is required", name),
+                "parameter": name,
+            })
+        })
+        .collect();
+
+    json_response(
+        StatusCode::OK,
+        json!({ "parameters": body, "errors": errors }),
+    )
+}
+
+fn create_item(state: &mut MockState, body: &Value) -> Response<Body> {
+    let connector_id = body["connectorId"].as_i64().unwrap_or_default();
+    let connector = match state.connector(connector_id) {
+        Some(connector) => connector.clone(),
+        None => return not_found("Connector"),
+    };
+
+    let id = state.next_id();
+    let steps = state.script_for(ConnectorId(connector_id as i32), &parameters(body));
+    let item = json!({
+        "id": id,
+        "connector": connector,
+        "status": ItemStatus::Updating,
+        "statusDetail": null,
+        "error": null,
+        "executionStatus": ExecutionStatus::Created,
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+        "lastUpdatedAt": null,
+        "parameter": null,
+        "webhookUrl": body["webhookUrl"],
+        "clientUserId": body["clientUserId"],
+        "userAction": null,
+        "consecutiveFailedLoginAttempts": 0,
+    });
+    state.items.insert(
+        id,
+        MockItem {
+            json: item.clone(),
+            steps: steps.into(),
+            current: None,
+        },
+    );
+
+    json_response(StatusCode::OK, item)
+}
+
+fn create_preauthorization(state: &mut MockState, body: &Value) -> Response<Body> {
+    let connector = match body["connectorId"]
+        .as_i64()
+        .and_then(|id| state.connector(id))
+    {
+        Some(connector) => connector.clone(),
+        None => return not_found("Connector"),
+    };
+    let mut recipients = Vec::new();
+    for recipient_id in body["recipientIds"].as_array().into_iter().flatten() {
+        match state
+            .recipients
+            .iter()
+            .find(|recipient| recipient["id"] == *recipient_id)
+        {
+            Some(recipient) => recipients.push(recipient.clone()),
+            None => return not_found("Recipient"),
+        }
+    }
+    if recipients.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "recipientIds is required");
+    }
+
+    let id = state.next_id();
+    let preauthorization = json!({
+        "id": id,
+        "status": SmartTransferPreauthorizationStatus::Created,
+        "consentUrl": format!("https://consent.pluggy.ai/{}", id),
+        "connector": connector,
+        "recipients": recipients,
+        "callbackUrls": body["callbackUrls"],
+        "clientPreauthorizationId": body["clientPreauthorizationId"],
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    });
+    state.preauthorizations.push(preauthorization.clone());
+
+    json_response(StatusCode::OK, preauthorization)
+}
+
+fn create_smart_transfer_payment(state: &mut MockState, body: &Value) -> Response<Body> {
+    let preauthorization = match body["preauthorizationId"]
+        .as_str()
+        .and_then(|id| find(&state.preauthorizations, id))
+    {
+        Some(preauthorization) => preauthorization,
+        None => return not_found("Preauthorization"),
+    };
+    if preauthorization["status"] != json!(SmartTransferPreauthorizationStatus::Completed) {
+        return error_response(StatusCode::BAD_REQUEST, "Preauthorization is not completed");
+    }
+    let has_recipient = preauthorization["recipients"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|recipient| recipient["id"] == body["recipientId"]);
+    if !has_recipient {
+        return not_found("Recipient");
+    }
+    if !body["amount"].as_f64().is_some_and(|amount| amount > 0.0) {
+        return error_response(StatusCode::BAD_REQUEST, "amount must be positive");
+    }
+
+    let payment = json!({
+        "id": state.next_id(),
+        "preauthorizationId": body["preauthorizationId"],
+        "recipientId": body["recipientId"],
+        "status": SmartTransferPaymentStatus::Created,
+        "amount": body["amount"],
+        "description": body["description"],
+        "clientPaymentId": body["clientPaymentId"],
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    });
+    state.smart_transfer_payments.push(payment.clone());
+
+    json_response(StatusCode::OK, payment)
+}
+
+fn create_boleto_connection(state: &mut MockState, body: &Value) -> Response<Body> {
+    if body["connectorId"]
+        .as_i64()
+        .and_then(|id| state.connector(id))
+        .is_none()
+    {
+        return not_found("Connector");
+    }
+    if body["credentials"]
+        .as_object()
+        .is_none_or(|credentials| credentials.is_empty())
+    {
+        return error_response(StatusCode::BAD_REQUEST, "credentials is required");
+    }
+
+    let connection = json!({
+        "id": state.next_id(),
+        "connectorId": body["connectorId"],
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    });
+    state.boleto_connections.push(connection.clone());
+
+    json_response(StatusCode::OK, connection)
+}
+
+fn issue_boleto(state: &mut MockState, body: &Value) -> Response<Body> {
+    let connection_exists = body["boletoConnectionId"]
+        .as_str()
+        .and_then(|id| find(&state.boleto_connections, id))
+        .is_some();
+    if !connection_exists {
+        return not_found("Boleto connection");
+    }
+    let boleto = &body["boleto"];
+    if !boleto["amount"].as_f64().is_some_and(|amount| amount > 0.0) {
+        return error_response(StatusCode::BAD_REQUEST, "amount must be positive");
+    }
+
+    let boleto = json!({
+        "id": state.next_id(),
+        "boletoConnectionId": body["boletoConnectionId"],
+        "status": BoletoStatus::Open,
+        "seuNumero": boleto["seuNumero"],
+        "nossoNumero": format!("{:08}", state.boletos.len() + 1),
+        "digitableLine": null,
+        "barcode": null,
+        "amount": boleto["amount"],
+        "amountPaid": null,
+        "dueDate": boleto["dueDate"],
+        "payer": boleto["payer"],
+        "fine": boleto["fine"],
+        "interest": boleto["interest"],
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    });
+    state.boletos.push(boleto.clone());
+
+    json_response(StatusCode::OK, boleto)
+}
+
+/// Charges the amount encoded in the boleto's digitable line.
+fn create_payment_request(state: &mut MockState, body: &Value) -> Response<Body> {
+    let line = match body["boleto"]["digitableLine"]
+        .as_str()
+        .map(DigitableLine::parse)
+    {
+        Some(Ok(line)) => line,
+        _ => return error_response(StatusCode::BAD_REQUEST, "Invalid digitable line"),
+    };
+    let amount = match line.to_barcode().amount() {
+        Some(amount) => amount,
+        None => return error_response(StatusCode::BAD_REQUEST, "Boleto has no amount"),
+    };
+
+    let payment_request = json!({
+        "id": state.next_id(),
+        "amount": amount.to_f64(),
+        "description": body["description"],
+        "status": PaymentRequestStatus::Created,
+        "paymentUrl": format!("https://pay.pluggy.ai/{}", line.as_str()),
+        "recipientId": null,
+        "boleto": { "digitableLine": line.as_str() },
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    });
+    state.payment_requests.push(payment_request.clone());
+
+    json_response(StatusCode::OK, payment_request)
+}
+
+fn find<'a>(values: &'a [Value], id: &str) -> Option<&'a Value> {
+    values.iter().find(|value| value["id"] == id)
+}
+
+fn parameters(body: &Value) -> HashMap<String, String> {
+    body["parameters"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect()
+}
+
+/// Pages `results` following the `page` and `pageSize` query parameters.
+fn page_response(results: Vec<Value>, query: &HashMap<String, String>) -> Response<Body> {
+    let number = |name: &str, default: usize| {
+        query
+            .get(name)
+            .and_then(|value| value.parse().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(default)
+    };
+    let page = number("page", 1);
+    let page_size = number("pageSize", MOCK_PAGE_SIZE);
+
+    json_response(
+        StatusCode::OK,
+        json!({
+            "total": results.len(),
+            "totalPages": results.len().div_ceil(page_size),
+            "page": page,
+            "results": results
+                .iter()
+                .skip((page - 1) * page_size)
+                .take(page_size)
+                .collect::<Vec<_>>(),
+        }),
+    )
+}
+
+fn not_found(resource: &str) -> Response<Body> {
+    error_response(StatusCode::NOT_FOUND, &format!("{} not found", resource))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(
+        status,
+        json!({ "code": status.as_u16(), "message": message }),
+    )
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn credentials() -> Value {
+    json!([
+        { "label": "User", "name": "user", "type": "text", "placeholder": "user-ok" },
+        { "label": "Password", "name": "password", "type": "password", "placeholder": "password-ok" },
+    ])
+}
+
+fn default_recipient() -> Value {
+    json!({
+        "id": MOCK_RECIPIENT_ID,
+        "taxNumber": "12345678000190",
+        "name": "Loja Exemplo LTDA",
+        "paymentInstitution": null,
+        "isDefault": true,
+        "account": { "branch": "0001", "number": "123456-7", "type": "CHECKING_ACCOUNT" },
+        "pixKey": null,
+        "createdAt": MOCK_DATE,
+        "updatedAt": MOCK_DATE,
+    })
+}
+
+fn itau_connector() -> Value {
+    json!({
+        "id": 201,
+        "name": "Itaú",
+        "institutionUrl": "https://www.itau.com.br",
+        "imageUrl": "https://cdn.pluggy.ai/assets/connector-icons/201.svg",
+        "primaryColor": "EC7000",
+        "type": "PERSONAL_BANK",
+        "country": "BR",
+        "credentials": credentials(),
+        "hasMFA": false,
+        "oauth": false,
+        "health": { "status": "ONLINE", "stage": null },
+        "products": ["ACCOUNTS", "CREDIT_CARDS", "TRANSACTIONS", "IDENTITY"],
+        "isOpenFinance": false,
+        "createdAt": MOCK_DATE,
+    })
+}
+
+fn pluggy_bank_connector() -> Value {
+    json!({
+        "id": 2,
+        "name": "Pluggy Bank",
+        "institutionUrl": "https://pluggy.ai",
+        "imageUrl": "https://cdn.pluggy.ai/assets/connector-icons/sandbox.svg",
+        "primaryColor": "ef294b",
+        "type": "PERSONAL_BANK",
+        "country": "BR",
+        "credentials": credentials(),
+        "hasMFA": false,
+        "oauth": false,
+        "health": { "status": "ONLINE", "stage": null },
+        "products": [
+            "ACCOUNTS",
+            "CREDIT_CARDS",
+            "TRANSACTIONS",
+            "INVESTMENTS",
+            "IDENTITY",
+            "PAYMENT_DATA",
+        ],
+        "isOpenFinance": false,
+        "createdAt": MOCK_DATE,
+    })
+}
+
+fn default_categories() -> Vec<Value> {
+    [
+        ("01000000", "Income", None),
+        ("01010000", "Salary", Some(("01000000", "Income"))),
+        ("05000000", "Transfers", None),
+        ("08000000", "Shopping", None),
+        (
+            "08010000",
+            "Online shopping",
+            Some(("08000000", "Shopping")),
+        ),
+    ]
+    .into_iter()
+    .map(|(id, description, parent)| {
+        json!({
+            "id": id,
+            "description": description,
+            "parentId": parent.map(|(id, _)| id),
+            "parentDescription": parent.map(|(_, description)| description),
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox_parameters() -> HashMap<String, String> {
+        HashMap::from([
+            ("user".to_string(), "user-ok".to_string()),
+            ("password".to_string(), "password-ok".to_string()),
+        ])
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_client_credentials() {
+        let server = MockServer::start().await;
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .base_url(server.url())
+            .transport(HyperTransport::http())
+            .build();
+
+        let error = client.create_api_key().await.unwrap_err();
+        assert_eq!(error.to_string(), "No api key found");
+    }
+
+    #[tokio::test]
+    async fn can_walk_item_through_default_script() {
+        let server = MockServer::start().await;
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &sandbox_parameters())
+            .await
+            .unwrap();
+        assert!(matches!(item.status, ItemStatus::Updating));
+        assert!(matches!(item.execution_status, ExecutionStatus::Created));
+
+        let mut item = client.get_item(&api_key, item.id).await.unwrap();
+        while item.status == ItemStatus::Updating {
+            item = client.get_item(&api_key, item.id).await.unwrap();
+        }
+
+        assert!(matches!(item.status, ItemStatus::Updated));
+        assert!(matches!(item.execution_status, ExecutionStatus::Success));
+
+        client.delete_item(&api_key, item.id).await.unwrap();
+        assert!(client.get_item(&api_key, item.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn can_script_item_errors_and_mfa() {
+        let server = MockServer::start().await;
+        server.script_items(
+            |_, parameters| parameters.get("password").map(String::as_str) == Some("wrong"),
+            vec![ItemStep::error(ExecutionErrorCodes::InvalidCredentials)],
+        );
+        server.script_connector_items(
+            ConnectorId(2),
+            vec![ItemStep::waiting_for_token(), ItemStep::success()],
+        );
+        let (client, api_key) = server.client_with_api_key().await.unwrap();
+
+        let mut parameters = sandbox_parameters();
+        parameters.insert("password".to_string(), "wrong".to_string());
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &parameters)
+            .await
+            .unwrap();
+        let item = client.get_item(&api_key, item.id).await.unwrap();
+
+        assert!(matches!(item.status, ItemStatus::LoginError));
+        assert!(matches!(
+            item.error.map(|error| error.code),
+            Some(ExecutionErrorCodes::InvalidCredentials)
+        ));
+        assert_eq!(item.consecutive_failed_login_attempts, 1);
+
+        let item = client
+            .create_item(&api_key, ConnectorId(2), &sandbox_parameters())
+            .await
+            .unwrap();
+        let item = client.get_item(&api_key, item.id).await.unwrap();
+        assert!(matches!(item.status, ItemStatus::WaitingUserInput));
+        assert_eq!(item.parameter.unwrap().name, "token");
+
+        let item = client.get_item(&api_key, item.id).await.unwrap();
+        assert!(matches!(item.status, ItemStatus::WaitingUserInput));
+
+        let token = HashMap::from([("token".to_string(), "123456".to_string())]);
+        let item = client
+            .update_item_mfa_credentials(&api_key, item.id, &token)
+            .await
+            .unwrap();
+        assert!(matches!(item.status, ItemStatus::Updated));
+    }
+}
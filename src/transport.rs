@@ -0,0 +1,302 @@
+//! HTTP transports `Client` sends its requests through.
+//!
+//! Requests and responses are plain `http` types with buffered bodies, so a
+//! transport only has to move bytes. Which implementations are available
+//! depends on the enabled features:
+//!
+//! - `native-tls` (default): `HyperTransport::native_tls`, hyper 0.14 with
+//!   OpenSSL/SChannel/Security.framework.
+//! - `rustls`: `HyperTransport::rustls`, hyper 0.14 with rustls and the
+//!   webpki roots.
+//! - `reqwest`: `ReqwestTransport`, reqwest with rustls.
+//!
+//! `MemoryTransport` is always available for unit tests.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use http::{HeaderMap, Method, Request, Response, StatusCode, Uri};
+
+pub type HttpRequest = Request<Vec<u8>>;
+pub type HttpResponse = Response<Vec<u8>>;
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HttpResponse, TransportError>> + Send + 'a>>;
+
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        (**self).send(request)
+    }
+}
+
+impl<T: HttpTransport + ?Sized> HttpTransport for Box<T> {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        (**self).send(request)
+    }
+}
+
+/// Transport used by `Client::new` and `ClientBuilder::build`: native-tls,
+/// then rustls, then reqwest, depending on the enabled features.
+pub fn default_transport() -> Arc<dyn HttpTransport> {
+    #[cfg(feature = "native-tls")]
+    return Arc::new(HyperTransport::native_tls());
+
+    #[cfg(all(not(feature = "native-tls"), feature = "rustls"))]
+    return Arc::new(HyperTransport::rustls());
+
+    #[cfg(all(
+        not(feature = "native-tls"),
+        not(feature = "rustls"),
+        feature = "reqwest"
+    ))]
+    return Arc::new(ReqwestTransport::new());
+
+    #[cfg(not(any(feature = "native-tls", feature = "rustls", feature = "reqwest")))]
+    return Arc::new(MissingTransport);
+}
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls", feature = "reqwest")))]
+struct MissingTransport;
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls", feature = "reqwest")))]
+impl HttpTransport for MissingTransport {
+    fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async {
+            Err(
+                "No HTTP transport: enable the native-tls, rustls or reqwest feature, \
+                 or set one with ClientBuilder::transport"
+                    .into(),
+            )
+        })
+    }
+}
+
+#[cfg(feature = "hyper")]
+pub use self::hyper_transport::HyperTransport;
+
+#[cfg(feature = "hyper")]
+mod hyper_transport {
+    use hyper::client::connect::{Connect, HttpConnector};
+    use hyper::{Body, Client};
+
+    use super::{HttpRequest, HttpTransport, TransportFuture};
+
+    pub struct HyperTransport<C = HttpConnector> {
+        client: Client<C, Body>,
+    }
+
+    impl HyperTransport {
+        /// Plain HTTP, for local servers such as `testing::MockServer`.
+        pub fn http() -> Self {
+            Self::with_connector(HttpConnector::new())
+        }
+    }
+
+    #[cfg(feature = "native-tls")]
+    impl HyperTransport<hyper_tls::HttpsConnector<HttpConnector>> {
+        pub fn native_tls() -> Self {
+            Self::with_connector(hyper_tls::HttpsConnector::new())
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    impl HyperTransport<hyper_rustls::HttpsConnector<HttpConnector>> {
+        /// HTTPS only. Use `HyperTransport::http` for plain HTTP.
+        pub fn rustls() -> Self {
+            let connector = hyper_rustls::HttpsConnectorBuilder::new()
+                .with_webpki_roots()
+                .https_only()
+                .enable_http1()
+                .build();
+
+            Self::with_connector(connector)
+        }
+    }
+
+    impl<C: Connect + Clone + Send + Sync + 'static> HyperTransport<C> {
+        pub fn with_connector(connector: C) -> Self {
+            Self {
+                client: Client::builder().build(connector),
+            }
+        }
+    }
+
+    impl<C: Connect + Clone + Send + Sync + 'static> HttpTransport for HyperTransport<C> {
+        fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+            Box::pin(async move {
+                let response = self.client.request(request.map(Body::from)).await?;
+                let (parts, body) = response.into_parts();
+                let body = hyper::body::to_bytes(body).await?;
+
+                Ok(http::Response::from_parts(parts, body.to_vec()))
+            })
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    pub fn new() -> Self {
+        Self::with_client(reqwest::Client::new())
+    }
+
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let response = self
+                .client
+                .request(parts.method, parts.uri.to_string())
+                .headers(parts.headers)
+                .body(body)
+                .send()
+                .await?;
+
+            let mut builder = Response::builder().status(response.status());
+            if let Some(headers) = builder.headers_mut() {
+                *headers = response.headers().clone();
+            }
+
+            Ok(builder.body(response.bytes().await?.to_vec())?)
+        })
+    }
+}
+
+/// A request received by a `MemoryTransport`.
+#[derive(Debug, Clone)]
+pub struct MemoryRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+type MemoryHandler = Box<dyn Fn(&MemoryRequest) -> HttpResponse + Send + Sync>;
+
+/// Answers requests with a handler function, without any network, and keeps
+/// the requests it received.
+pub struct MemoryTransport {
+    handler: MemoryHandler,
+    requests: Mutex<Vec<MemoryRequest>>,
+}
+
+impl MemoryTransport {
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&MemoryRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        Self {
+            handler: Box::new(handler),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn requests(&self) -> Vec<MemoryRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl HttpTransport for MemoryTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        let (parts, body) = request.into_parts();
+        let request = MemoryRequest {
+            method: parts.method,
+            uri: parts.uri,
+            headers: parts.headers,
+            body,
+        };
+        let response = (self.handler)(&request);
+        self.requests.lock().unwrap().push(request);
+
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+/// JSON response for `MemoryTransport` handlers.
+pub fn json_response(status: StatusCode, body: &serde_json::Value) -> HttpResponse {
+    let mut response = Response::new(body.to_string().into_bytes());
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// Path with the ids replaced by `{id}`, so requests to the same endpoint
+/// share a name in traces and metrics.
+pub fn endpoint(path: &str) -> String {
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment.chars().any(|c| c.is_ascii_digit()) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect();
+
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Client;
+
+    #[tokio::test]
+    async fn can_send_requests_through_memory_transport() {
+        let transport = Arc::new(MemoryTransport::new(|request| {
+            assert_eq!(request.uri.path(), "/auth");
+            json_response(StatusCode::OK, &json!({ "apiKey": "memory-api-key" }))
+        }));
+        let client = Client::builder("id".to_string(), "secret".to_string())
+            .transport(transport.clone())
+            .build();
+
+        assert_eq!(client.create_api_key().await.unwrap(), "memory-api-key");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::POST);
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["clientId"], "id");
+    }
+
+    #[test]
+    fn can_name_endpoints() {
+        assert_eq!(
+            endpoint("/items/e22c7308-7031-47f0-88a3-462f44d96f70/mfa"),
+            "/items/{id}/mfa"
+        );
+        assert_eq!(endpoint("/connectors/201"), "/connectors/{id}");
+        assert_eq!(endpoint("/categories/rules"), "/categories/rules");
+    }
+}